@@ -3,8 +3,10 @@ use core::{future::Future};
 
 
 use std::rc::Rc;
-#[cfg(feature = "smol_executor")]
+#[cfg(any(feature = "smol_executor", feature = "simulation_executor", feature = "rayon"))]
 use std::sync::Arc;
+#[cfg(any(feature = "async_std_executor", feature = "async_global_executor"))]
+use std::sync::atomic::{AtomicBool, Ordering};
 
 mod join_handle;
 pub use join_handle::*;
@@ -18,123 +20,317 @@ use ExecutorInnerHandle::*;
 mod local_agnostic_executor;
 pub use local_agnostic_executor::*;
 
+#[cfg(feature = "simulation_executor")]
+mod simulation_executor;
+
+#[cfg(feature = "scoped")]
+mod scope;
+#[cfg(feature = "scoped")]
+pub use scope::Scope;
+
+mod abort_handle;
+pub use abort_handle::{AbortHandle, AbortOnDropHandle};
+
+mod shared_join_handle;
+pub use shared_join_handle::SharedJoinHandle;
+
+mod task_set;
+pub use task_set::TaskSet;
+
+mod shutdown;
+pub use shutdown::{ShutdownHandle, ShutdownReport, ShutdownSignal};
+pub(crate) use shutdown::TaskTracker;
+#[cfg(feature = "time")]
+pub(crate) use shutdown::RunnableWaker;
+
+mod current;
+#[cfg(feature = "time")]
+pub(crate) use current::current_clock;
+
+mod kind;
+pub use kind::{ExecutorKind, ExecutorKindError, EXECUTOR_ENV_VAR};
+
+mod config;
+pub use config::{RuntimeConfig, RuntimeOption};
+
+#[cfg(feature = "futures_executor")]
+mod blocking_pool;
+
+#[cfg(feature = "compute")]
+mod compute_pool;
+
+#[cfg(feature = "rayon")]
+mod rayon_pool;
+
+mod global;
+pub use global::{with_global_executor, with_global_executor_async, set_thread_global_executor, WithGlobalExecutor};
+
+mod task;
+pub use task::{TaskId, TaskBuilder, LocalTaskBuilder, current_task_id, current_task_name};
+pub(crate) use task::TaskSlot;
+
 
 /// It lets you build an AgnosticExecutorManager for a concrete executor
-pub struct AgnosticExecutorBuilder {}
+#[derive(Debug)]
+pub struct AgnosticExecutorBuilder {
+    config: RuntimeConfig
+}
 
 impl AgnosticExecutorBuilder {
 
+    /// Sets the configuration of the thread pools of the executor.
+    /// Each executor maps the options it supports, and the ignored ones are available from AgnosticExecutorManager::ignored_options.
+    pub fn with_config(mut self, config: RuntimeConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// A manager for the executor of the provided kind, with its default settings and the configuration of the builder.
+    /// It returns an error if the feature of the executor is not enabled. The simulation executor uses the seed 0.
+    #[allow(unreachable_patterns)]
+    pub fn use_executor(self, kind: ExecutorKind) -> Result<AgnosticExecutorManager, ExecutorKindError> {
+        match kind {
+            #[cfg(feature = "tokio_executor")]
+            ExecutorKind::Tokio => Ok(self.use_tokio_executor()),
+            #[cfg(feature = "async_std_executor")]
+            ExecutorKind::AsyncStd => Ok(self.use_async_std_executor()),
+            #[cfg(feature = "smol_executor")]
+            ExecutorKind::Smol => Ok(self.use_smol_executor(None)),
+            #[cfg(feature = "async_global_executor")]
+            ExecutorKind::AsyncGlobal => Ok(self.use_async_global_executor()),
+            #[cfg(feature = "simulation_executor")]
+            ExecutorKind::Simulation => Ok(self.use_simulation_executor(0)),
+            #[cfg(feature = "futures_executor")]
+            ExecutorKind::Futures => Ok(self.use_futures_executor()),
+            #[cfg(feature = "wasm_bindgen_executor")]
+            ExecutorKind::WasmBindgen => Ok(self.use_wasm_bindgen_executor()),
+            kind => Err(ExecutorKindError::NotEnabled(kind))
+        }
+    }
+
+    /// A manager for the executor named in the AGNOSTIC_EXECUTOR environment variable, like AGNOSTIC_EXECUTOR=smol.
+    /// It allows switching between the enabled executors without recompiling, and it returns an error if the variable is not set or the executor is unknown or not enabled.
+    pub fn from_env(self) -> Result<AgnosticExecutorManager, ExecutorKindError> {
+        let name = std::env::var(EXECUTOR_ENV_VAR).map_err(|_| ExecutorKindError::NotSet)?;
+        self.use_executor(name.parse()?)
+    }
+
+    // Every option is ignored when the runtime is provided already built
+    #[cfg_attr(not(any(feature = "tokio_executor", feature = "simulation_executor", feature = "wasm_bindgen_executor")), allow(dead_code))]
+    fn ignore_config(&self, mut manager: AgnosticExecutorManager) -> AgnosticExecutorManager {
+        manager.ignored_options = self.config.options();
+        manager
+    }
+
     /// A manager for a default multi-threaded Tokio executor
     #[cfg(feature = "tokio_executor")]
     pub fn use_tokio_executor(self) -> AgnosticExecutorManager {
-        let rt = tokio::runtime::Builder::new_multi_thread()
-            .enable_all()
-            .build()
-            .expect("Error creating tokio runtime");
-        self.use_tokio_executor_with_runtime(rt)
+        let (mut builder, ignored) = self.config.tokio_builder(true);
+        let rt = builder.build().expect("Error creating tokio runtime");
+        let mut manager = self.use_tokio_executor_with_runtime(rt);
+        manager.ignored_options = ignored;
+        manager
+    }
+
+    /// A manager for a single-threaded Tokio executor.
+    /// Every task, including the ones spawned with the LocalAgnosticExecutor, runs on the thread that starts the executor.
+    /// Blocking tasks still run on Tokio's blocking thread pool.
+    #[cfg(feature = "tokio_executor")]
+    pub fn use_tokio_current_thread_executor(self) -> AgnosticExecutorManager {
+        let (mut builder, ignored) = self.config.tokio_builder(false);
+        let rt = builder.build().expect("Error creating tokio runtime");
+        let mut manager = self.use_tokio_executor_with_runtime(rt);
+        manager.ignored_options = ignored;
+        manager
     }
 
     /// A manager for a provided Tokio executor
     #[cfg(feature = "tokio_executor")]
     pub fn use_tokio_executor_with_runtime(self, rt: tokio::runtime::Runtime) -> AgnosticExecutorManager {
         let handle = rt.handle().clone();
-        AgnosticExecutorManager { 
-            inner_handle: TokioHandle(handle),
-            inner_runtime: TokioRuntime(rt),
-            local_inner_runtime: LocalExecutorInnerRuntime::TokioRuntime(tokio::task::LocalSet::new()),
-            local_inner_handle: LocalExecutorInnerHandle::TokioHandle,
-            finish_callback: None
+        self.ignore_config(AgnosticExecutorManager::new(
+            TokioRuntime(rt),
+            TokioHandle(handle),
+            LocalExecutorInnerRuntime::TokioRuntime(tokio::task::LocalSet::new()),
+            LocalExecutorInnerHandle::TokioHandle
+        ))
+    }
+
+    // Async Std and async_global_executor share a pool that can only be configured once, before it starts.
+    // Every option is ignored once a previous manager has configured it, or might have started it with the default settings
+    #[cfg(any(feature = "async_std_executor", feature = "async_global_executor"))]
+    fn init_async_global_executor(&self, env_var: Option<&'static str>) -> Vec<RuntimeOption> {
+        let (config, ignored) = self.config.global_executor_config(env_var);
+        if ASYNC_GLOBAL_EXECUTOR_USED.swap(true, Ordering::SeqCst) {
+            return self.config.options();
         }
+        if !self.config.options().is_empty() {
+            async_global_executor::init_with_config(config);
+        }
+        ignored
     }
 
-    /// A manager for an Async Std executor
+    /// A manager for an Async Std executor.
+    /// Async Std shares its thread pool with the whole process, so the configuration only applies to the first Async Std or async_global_executor manager.
     #[cfg(feature = "async_std_executor")]
     pub fn use_async_std_executor(self) -> AgnosticExecutorManager {
-        AgnosticExecutorManager { 
-            inner_handle: AsyncStdHandle,
-            inner_runtime: AsyncStdRuntime,
-            local_inner_runtime: LocalExecutorInnerRuntime::AsyncStdRuntime,
-            local_inner_handle: LocalExecutorInnerHandle::AsyncStdHandle,
-            finish_callback: None
-        }
+        let ignored = self.init_async_global_executor(Some("ASYNC_STD_THREAD_COUNT"));
+        let mut manager = AgnosticExecutorManager::new(
+            AsyncStdRuntime,
+            AsyncStdHandle,
+            LocalExecutorInnerRuntime::AsyncStdRuntime,
+            LocalExecutorInnerHandle::AsyncStdHandle
+        );
+        manager.ignored_options = ignored;
+        manager
     }
 
     /// A manager for a Smol executor.
-    /// If num_threads is not provided, it defaults default to the number of logical cores.
+    /// If num_threads is not provided, it defaults to the worker threads of the configuration, or to the number of logical cores.
     #[cfg(feature = "smol_executor")]
     pub fn use_smol_executor(self, num_threads: Option<usize>) -> AgnosticExecutorManager {
         let rt = Arc::new(async_executor::Executor::new());
         let handle = rt.clone();
-        let num_threads = num_threads.unwrap_or(num_cpus::get());
+        let (threads, ignored) = self.config.smol_threads(num_threads);
         let local = Rc::new(async_executor::LocalExecutor::new());
-        AgnosticExecutorManager { 
-            inner_handle: SmolHandle(handle),
-            inner_runtime: SmolRuntime(rt, num_threads),
-            local_inner_runtime: LocalExecutorInnerRuntime::SmolRuntime(local.clone()),
-            local_inner_handle: LocalExecutorInnerHandle::SmolHandle(local),
-            finish_callback: None
-        }
+        let mut manager = AgnosticExecutorManager::new(
+            SmolRuntime(rt, threads),
+            SmolHandle(handle),
+            LocalExecutorInnerRuntime::SmolRuntime(local.clone()),
+            LocalExecutorInnerHandle::SmolHandle(local)
+        );
+        manager.ignored_options = ignored;
+        manager
+    }
+
+    /// A manager for the global executor from the async_global_executor crate.
+    /// The thread pool is shared with the whole process, so the configuration only applies to the first Async Std or async_global_executor manager, and only if no other library started the pool before.
+    #[cfg(feature = "async_global_executor")]
+    pub fn use_async_global_executor(self) -> AgnosticExecutorManager {
+        let ignored = self.init_async_global_executor(None);
+        let mut manager = AgnosticExecutorManager::new(
+            AsyncGlobalRuntime,
+            AsyncGlobalHandle,
+            LocalExecutorInnerRuntime::AsyncGlobalRuntime,
+            LocalExecutorInnerHandle::AsyncGlobalHandle
+        );
+        manager.ignored_options = ignored;
+        manager
+    }
+
+    /// A manager for a deterministic single-threaded executor, useful to reproduce ordering bugs in tests.
+    /// Every task, including blocking ones, runs on the thread that starts the executor, and the next task to poll is picked at random from the given seed.
+    /// The same seed always reproduces the same interleaving, as long as the tasks are only woken by other tasks and not by external events like timers.
+    #[cfg(feature = "simulation_executor")]
+    pub fn use_simulation_executor(self, seed: u64) -> AgnosticExecutorManager {
+        let executor = Arc::new(simulation_executor::SimulationExecutor::new(seed));
+        self.ignore_config(AgnosticExecutorManager::new(
+            SimulationRuntime(executor.clone()),
+            SimulationHandle(executor.clone()),
+            LocalExecutorInnerRuntime::SimulationRuntime,
+            LocalExecutorInnerHandle::SimulationHandle(executor, std::marker::PhantomData)
+        ))
     }
 
     /// A manager for a default Threadpool executor from the futures crate.
+    /// Blocking tasks run on a separate pool owned by this crate, configured with the blocking options of the configuration.
     #[cfg(feature = "futures_executor")]
     pub fn use_futures_executor(self) -> AgnosticExecutorManager {
-        let rt = futures::executor::ThreadPool::new().expect("Error creating the futures threadpool");
-        self.use_futures_executor_with_runtime(rt)
+        let (rt, ignored) = self.config.futures_thread_pool();
+        let mut manager = self.use_futures_executor_with_runtime(rt);
+        manager.ignored_options = ignored;
+        manager
     }
 
     /// A manager for a provided executor from the futures crate.
+    /// Blocking tasks run on a separate pool owned by this crate, so the configuration only applies to it.
     #[cfg(feature = "futures_executor")]
     pub fn use_futures_executor_with_runtime(self, rt: futures::executor::ThreadPool) -> AgnosticExecutorManager {
         let handle = rt.clone();
+        let (blocking_pool, ignored) = self.config.blocking_pool();
         let local = futures::executor::LocalPool::new();
         let local_spawner = local.spawner();
-        AgnosticExecutorManager { 
-            inner_handle: FuturesHandle(handle),
-            inner_runtime: FuturesRuntime(rt),
-            local_inner_runtime: LocalExecutorInnerRuntime::FuturesRuntime(local),
-            local_inner_handle: LocalExecutorInnerHandle::FuturesHandle(local_spawner),
-            finish_callback: None
-        }
+        let mut manager = AgnosticExecutorManager::new(
+            FuturesRuntime(rt),
+            FuturesHandle(handle, blocking_pool),
+            LocalExecutorInnerRuntime::FuturesRuntime(local),
+            LocalExecutorInnerHandle::FuturesHandle(local_spawner)
+        );
+        manager.ignored_options = ignored;
+        manager
     }
 
-    /// A manager for a wasm executor from the wasm_bindgen_futures crate
+    /// A manager for a wasm executor from the wasm_bindgen_futures crate.
+    /// There are no threads on wasm, so every option of the configuration is ignored.
     #[cfg(feature = "wasm_bindgen_executor")]
     pub fn use_wasm_bindgen_executor(self) -> AgnosticExecutorManager {
-        AgnosticExecutorManager { 
-            inner_handle: WasmBindgenHandle,
-            inner_runtime: WasmBindgenRuntime,
-            local_inner_runtime: LocalExecutorInnerRuntime::WasmBindgenRuntime,
-            local_inner_handle: LocalExecutorInnerHandle::WasmBindgenHandle,
-            finish_callback: None
-        }
+        self.ignore_config(AgnosticExecutorManager::new(
+            WasmBindgenRuntime,
+            WasmBindgenHandle,
+            LocalExecutorInnerRuntime::WasmBindgenRuntime,
+            LocalExecutorInnerHandle::WasmBindgenHandle
+        ))
     }
 }
 
 use once_cell::sync::OnceCell;
 static GLOBAL_EXECUTOR: OnceCell<AgnosticExecutor>  = OnceCell::new();
 
+#[cfg(any(feature = "async_std_executor", feature = "async_global_executor"))]
+static ASYNC_GLOBAL_EXECUTOR_USED: AtomicBool = AtomicBool::new(false);
+
 /// An AgnosticExecutorManager is configured on creation for a specific executor and it allows you get the the general and local executor, set it as global executor, and of course, start the executor.
 pub struct AgnosticExecutorManager {
     inner_runtime: ExecutorInner,
     inner_handle: ExecutorInnerHandle,
     local_inner_runtime: LocalExecutorInnerRuntime,
     local_inner_handle: LocalExecutorInnerHandle,
-    finish_callback: Option<Box<dyn FnOnce() -> () + 'static>>
+    finish_callback: Option<Box<dyn FnOnce() -> () + 'static>>,
+    tracker: TaskTracker,
+    ignored_options: Vec<RuntimeOption>,
+    #[cfg(feature = "time")]
+    clock: Option<crate::time::VirtualClock>,
+    grace_period: Option<std::time::Duration>,
+    #[cfg(feature = "rayon")]
+    rayon_pool: Option<Arc<rayon::ThreadPool>>
 }
 
 impl AgnosticExecutorManager {
+    fn new(inner_runtime: ExecutorInner, inner_handle: ExecutorInnerHandle, local_inner_runtime: LocalExecutorInnerRuntime, local_inner_handle: LocalExecutorInnerHandle) -> Self {
+        AgnosticExecutorManager {
+            inner_runtime,
+            inner_handle,
+            local_inner_runtime,
+            local_inner_handle,
+            finish_callback: None,
+            tracker: TaskTracker::new(),
+            ignored_options: Vec::new(),
+            #[cfg(feature = "time")]
+            clock: None,
+            grace_period: None,
+            #[cfg(feature = "rayon")]
+            rayon_pool: None
+        }
+    }
+
     /// Get the executor of this manager as an AgnosticExecutor.
     /// This is needed if you need to spawn new tasks, and it be easily stored, cloned and send across threads to have it available where ever you need to spawn a new tasks or interact with the executor. 
     pub fn get_executor(&self) -> AgnosticExecutor {
-        AgnosticExecutor { inner: self.inner_handle.clone() }
+        AgnosticExecutor { 
+            inner: self.inner_handle.clone(),
+            tracker: self.tracker.clone(),
+            #[cfg(feature = "time")]
+            clock: self.clock.clone(),
+            #[cfg(feature = "rayon")]
+            rayon_pool: self.rayon_pool.clone()
+        }
     }
 
     /// Get the local executor of this manager as a LocalAgnosticExecutor.
     /// A local executor is similar to the general executor but it allows to spawn tasks that are not send.
     /// The drawback is that, even tough you can store and clone a LocalAgnosticExecutor, you cannot send it to other threads.
     pub fn get_local_executor(&mut self) -> LocalAgnosticExecutor {
-        LocalAgnosticExecutor { inner: self.local_inner_handle.clone() }
+        LocalAgnosticExecutor { inner: self.local_inner_handle.clone(), tracker: self.tracker.clone(), executor: self.get_executor() }
     }
 
     /// Sets up a callback to be called when the executor finishes. It can only be called once.
@@ -143,97 +339,228 @@ impl AgnosticExecutorManager {
         self.finish_callback = Some(Box::new(cb));
     }
 
-    /// Sets this executor as the global executor to be used with the global crate functions get_global_executor, spawn and spawn_blocking.
+    /// Makes the time utilities of this executor use a virtual clock instead of the real one.
+    /// It must be called before getting the executors, as only the ones obtained afterwards use the clock.
+    /// If the clock was created with auto advance, it advances while the executor is started and every task is waiting for it.
+    #[cfg(feature = "time")]
+    pub fn set_virtual_clock(&mut self, clock: crate::time::VirtualClock) {
+        if clock.is_auto_advance() {
+            self.tracker.count_runnable();
+        }
+        self.clock = Some(clock);
+    }
+
+    /// Makes the rayon helpers of this executor use the provided pool instead of the global pool of rayon, so it can be shared with the rest of the application.
+    /// It must be called before getting the executors, as only the ones obtained afterwards use the pool.
+    #[cfg(feature = "rayon")]
+    pub fn set_rayon_pool(&mut self, pool: Arc<rayon::ThreadPool>) {
+        self.rayon_pool = Some(pool);
+    }
+
+    /// Returns the options of the RuntimeConfig that the executor doesn't support, or that couldn't be applied because the runtime was provided already built.
+    pub fn ignored_options(&self) -> &[RuntimeOption] {
+        &self.ignored_options
+    }
+
+    /// Get a handle to request the graceful shutdown of this executor from any thread.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle { tracker: self.tracker.clone() }
+    }
+
+    /// Sets the maximum time to wait for the root future and the spawned tasks to finish once the shutdown is requested.
+    /// When it expires the remaining tasks are cancelled, including the root future, and start returns. By default it waits for every task to finish.
+    /// Blocking tasks cannot be interrupted, so the ones still running when it expires are left running in the background.
+    /// It's measured with the clock of the executor when the time feature is enabled, and with a plain thread otherwise, so wasm needs the time feature.
+    #[cfg(any(feature = "time", not(feature = "wasm_bindgen_executor")))]
+    pub fn set_shutdown_grace_period(&mut self, grace_period: std::time::Duration) {
+        self.grace_period = Some(grace_period);
+    }
+
+    /// Sets this executor as the global executor to be used with the global crate functions current_global_executor, spawn and spawn_blocking.
     /// You still need to start the executor after setting it as global.
-    /// This can only be called once.
+    /// This can only be called once, use with_global_executor or set_as_thread_global to replace it on a given thread.
     pub fn set_as_global(&self) {
         GLOBAL_EXECUTOR.set(self.get_executor()).expect("Global executor already set");
     }
 
+    /// Sets this executor as the default global executor of the current thread, replacing the previous one.
+    /// It takes precedence over the process-wide global executor on this thread, and it can be called many times.
+    pub fn set_as_thread_global(&self) {
+        set_thread_global_executor(Some(self.get_executor()));
+    }
+
     /// Start the executor with the provided future. 
     /// This future doesn't need to be Send, but it needs to be 'static. You can use async move {...} to achieve this if needed.
     /// Note that in wasm the call might finish before the future has completely executed due to the non-blocking nature of the environment, so don't depend on this.
     /// With the other executors you can depend on the fact that start is blocking, but the on_finish callback is called anyway.
-    pub fn start<F>(mut self, future: F) where F: Future<Output = ()> + 'static {
+    pub fn start<F>(self, future: F) where F: Future<Output = ()> + 'static {
+        #[cfg(feature = "wasm_bindgen_executor")]
+        if matches!(self.inner_runtime, WasmBindgenRuntime) {
+            drop(self.run_async(future)); // Dropping the handle doesn't cancel the root future
+            return;
+        }
+
+        // When the shutdown grace period expires the root future is cancelled too, and start just returns
+        let _ = self.run_root(future);
+    }
+
+    /// Runs the executor with the provided future until it completes, and returns its output.
+    /// It blocks like start, so it's useful to return the exit status of the application straight from the async root.
+    /// It panics if the root future is cancelled because the shutdown grace period expired, use try_run to handle that case.
+    /// It panics on wasm, where run_async must be used instead.
+    pub fn run<F, T>(self, future: F) -> T where F: Future<Output = T> + 'static {
+        match self.try_run(future) {
+            Ok(output) => output,
+            Err(e) => panic!("The root future didn't complete: {}", e)
+        }
+    }
+
+    /// Like run, but it returns a cancelled JoinError if the root future is cancelled because the shutdown grace period expired.
+    pub fn try_run<F, T>(self, future: F) -> Result<T, JoinError> where F: Future<Output = T> + 'static {
+        self.run_root(future).ok_or_else(JoinError::cancelled)
+    }
+
+    // Blocks on the root future, returning None if it was cancelled by the shutdown
+    fn run_root<F, T>(mut self, future: F) -> Option<T> where F: Future<Output = T> + 'static {
         let cb = self.finish_callback.take();
-        let finish_cb = || {
-            if let Some(cb) = cb {
-                cb();
-            }
-        };
+        let future = self.wrap_root(future);
 
-        match (self.inner_runtime, self.local_inner_runtime) {
+        let output: Option<T> = match (self.inner_runtime, self.local_inner_runtime) {
             #[cfg(feature = "tokio_executor")]
             (TokioRuntime(runtime), LocalExecutorInnerRuntime::TokioRuntime(localset)) => {
-                runtime.block_on(async move {
-                    localset.run_until(future).await;
-                });
-                finish_cb();
+                let output = runtime.block_on(localset.run_until(future));
+                drop(localset);
+                if self.tracker.is_cancelled() {
+                    // Dropping the runtime would wait for the blocking tasks, so the ones still running are left behind like on the other executors
+                    runtime.shutdown_background();
+                }
+                output
             },
             #[cfg(feature = "async_std_executor")]
             (AsyncStdRuntime, _) => {
-                async_std::task::block_on(future);
-                finish_cb();
+                async_std::task::block_on(future)
             },
             #[cfg(feature = "smol_executor")]
-            (SmolRuntime(executor, num_threads),  LocalExecutorInnerRuntime::SmolRuntime(local)) => {
-                if num_threads > 1 {
+            (SmolRuntime(executor, threads),  LocalExecutorInnerRuntime::SmolRuntime(local)) => {
+                if threads.num_threads > 1 {
                     let (signal, shutdown) = async_channel::unbounded::<()>();
-                    easy_parallel::Parallel::new()
-                        .each(0..num_threads, |_| futures_lite::future::block_on(executor.run(shutdown.recv())))
+                    let (_, output) = easy_parallel::Parallel::new()
+                        .each(0..threads.num_threads, |_| threads.run(|| futures_lite::future::block_on(executor.run(shutdown.recv()))))
                         .finish(|| {
                             futures_lite::future::block_on(async {
-                                local.run(future).await;
+                                let output = local.run(future).await;
                                 drop(signal);
-                            });
-                            finish_cb();
+                                output
+                            })
                         });
+                    output
                 } else {
-                    futures_lite::future::block_on(local.run(future));
-                    finish_cb();
+                    // With a single thread the local executor has to drive the general one too, or spawned tasks would never run
+                    futures_lite::future::block_on(executor.run(local.run(future)))
                 }
             },
+            #[cfg(feature = "async_global_executor")]
+            (AsyncGlobalRuntime, _) => {
+                async_global_executor::block_on(future)
+            },
+            #[cfg(feature = "simulation_executor")]
+            (SimulationRuntime(executor), _) => {
+                let output = executor.block_on(future);
+                executor.clear();
+                output
+            },
             #[cfg(feature = "futures_executor")]
             (FuturesRuntime(_), LocalExecutorInnerRuntime::FuturesRuntime(mut local)) => {
-                local.run_until(future);
-                finish_cb();
+                local.run_until(future)
             },
             #[cfg(feature = "wasm_bindgen_executor")]
             (WasmBindgenRuntime, _) => {
-                wasm_bindgen_futures::spawn_local(async move {
-                    future.await;
-                    finish_cb();
-                });
+                panic!("The wasm executor cannot block, use run_async instead.");
             },
             _ => { panic!("Couldn't start the executor."); }
+        };
+
+        if let Some(cb) = cb {
+            cb();
+        }
+
+        output
+    }
+
+    /// Starts the wasm executor with the provided future, returning a handle that resolves to its output.
+    /// It doesn't block, so the handle is the way to get the output of the root future on wasm. The handle can be dropped without cancelling it.
+    /// It panics on the other executors, where run must be used instead.
+    #[cfg(feature = "wasm_bindgen_executor")]
+    pub fn run_async<F, T>(mut self, future: F) -> JoinHandle<T> where F: Future<Output = T> + 'static, T: 'static {
+        let cb = self.finish_callback.take();
+        let future = self.wrap_root(catch_unwind_future(future));
+        let future = shutdown::untracked(async move {
+            let output = future.await;
+            if let Some(cb) = cb {
+                cb();
+            }
+            output.unwrap_or_else(|| Err(JoinError::cancelled()))
+        });
+        let slot = future.slot();
+
+        match self.inner_runtime {
+            WasmBindgenRuntime => {
+                use futures::future::FutureExt;
+                let (future, handle) = future.remote_handle();
+                wasm_bindgen_futures::spawn_local(future);
+                JoinHandle { inner: JoinHandleInner::<T>::RemoteHandle(Some(handle)), slot }
+            },
+            _ => { panic!("Only the wasm executor can run without blocking, use run instead."); }
         }
     }
+
+    // Adds the shutdown supervision, the virtual clock driver and the current executor to the root future
+    fn wrap_root<F, T>(&self, future: F) -> impl Future<Output = Option<T>> where F: Future<Output = T> {
+        let future = current::with_executor(&self.get_executor(), future);
+
+        let grace_period = self.grace_period.map(|grace_period| (self.get_executor(), grace_period));
+        let future = shutdown::supervise(future, self.tracker.clone(), grace_period);
+
+        #[cfg(feature = "time")]
+        let future = crate::time::with_auto_advance(self.clock.as_ref(), &self.tracker, future);
+
+        future
+    }
 }
 
 /// The base function to create a new concrete AgnosticExecutor.
 /// Use the builder to specify the concrete executor, and then the manager to get access the executor and start it.
 pub fn new_agnostic_executor() -> AgnosticExecutorBuilder {
-    AgnosticExecutorBuilder {}
+    AgnosticExecutorBuilder { config: RuntimeConfig::default() }
 }
 
-/// Checks if there is a global executor already set
+/// Checks if there is a global executor available on the current thread, either overridden for the thread or set process-wide.
 pub fn check_global_executor() -> bool {
-    GLOBAL_EXECUTOR.get().is_some()
+    global::overridden_global_executor().is_some() || GLOBAL_EXECUTOR.get().is_some()
 }
 
-/// Gets the executor set as the global executor.
+/// Gets the executor set process-wide as the global executor with set_as_global.
+/// It ignores the executors set with with_global_executor and set_thread_global_executor, which are not 'static.
 /// It might panic if no executor is set.
+#[deprecated(since = "0.2.3", note = "it ignores the overridden global executors, use current_global_executor instead")]
 pub fn get_global_executor() -> &'static AgnosticExecutor {
     GLOBAL_EXECUTOR.get().expect("No global executor set")
 }
 
+/// Gets the global executor of the current thread.
+/// It's the executor set with with_global_executor if any, then the default of the thread, and finally the one set process-wide with set_as_global.
+/// It might panic if no executor is set.
+pub fn current_global_executor() -> AgnosticExecutor {
+    global::overridden_global_executor().as_deref().or_else(|| GLOBAL_EXECUTOR.get()).expect("No global executor set").clone()
+}
+
 /// Spawn a future on the global executor
 pub fn spawn<F, T>(future: F) -> JoinHandle<T>
     where
         F: Future<Output = T> + Send + 'static,
         T: Send + 'static,
 {
-    get_global_executor().spawn(future)
+    current_global_executor().spawn(future)
 }
 
 /// Runs the provided closure on the global executor, and when possible, it does it in a way that doesn't block concurrent tasks.
@@ -242,7 +569,7 @@ where
     F: FnOnce() -> T + Send + 'static,
     T: Send + 'static,
 {
-    get_global_executor().spawn_blocking(task)
+    current_global_executor().spawn_blocking(task)
 }
 
 /// Runs and blocks until completion on the global executor.
@@ -251,5 +578,5 @@ where
 /// To be agnostic on the supported platforms use the block_on feature. If you enable it in WASM it will panic at runtime.
 #[cfg(feature = "block_on")]
 pub fn block_on<F: Future>(future: F) -> F::Output {
-    get_global_executor().block_on(future)
+    current_global_executor().block_on(future)
 }
\ No newline at end of file
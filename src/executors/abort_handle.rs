@@ -0,0 +1,108 @@
+use core::{
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+    future::Future
+};
+use std::sync::Arc;
+
+use super::{JoinHandle, JoinError, TaskSlot};
+
+#[derive(Clone)]
+enum AbortHandleInner {
+    #[cfg(feature = "tokio_executor")]
+    Tokio(tokio::task::AbortHandle),
+    Slot(Arc<TaskSlot>)
+}
+
+/// A handle to cancel a spawned task from anywhere, without consuming its JoinHandle.
+/// It can be cloned and sent to other threads, and the JoinHandle can still be awaited to get a cancelled JoinError.
+#[derive(Clone)]
+pub struct AbortHandle {
+    inner: AbortHandleInner
+}
+
+impl fmt::Debug for AbortHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AbortHandle").field("finished", &self.is_finished()).finish()
+    }
+}
+
+impl AbortHandle {
+    /// Cancels the task. Async tasks are cancelled the next time they are polled, and blocking tasks only if they didn't start yet.
+    /// Aborting a task that already finished does nothing.
+    pub fn abort(&self) {
+        match &self.inner {
+            #[cfg(feature = "tokio_executor")]
+            AbortHandleInner::Tokio(handle) => handle.abort(),
+            AbortHandleInner::Slot(slot) => slot.abort()
+        }
+    }
+
+    /// Checks if the task has finished, either because it completed, panicked or was cancelled.
+    pub fn is_finished(&self) -> bool {
+        match &self.inner {
+            #[cfg(feature = "tokio_executor")]
+            AbortHandleInner::Tokio(handle) => handle.is_finished(),
+            AbortHandleInner::Slot(slot) => slot.is_finished()
+        }
+    }
+}
+
+impl<T> JoinHandle<T> {
+    /// Returns a new AbortHandle that can be used to cancel the task independently of this handle.
+    pub fn abort_handle(&self) -> AbortHandle {
+        let inner = match &self.inner {
+            #[cfg(feature = "tokio_executor")]
+            super::JoinHandleInner::<T>::Tokio(Some(handle)) => AbortHandleInner::Tokio(handle.abort_handle()),
+            _ => AbortHandleInner::Slot(self.slot.clone())
+        };
+        AbortHandle { inner }
+    }
+
+    /// Converts the handle into a guard that cancels the task when it's dropped, instead of detaching it.
+    /// It's useful for request scoped work, that shouldn't outlive the request that started it.
+    pub fn abort_on_drop(self) -> AbortOnDropHandle<T> {
+        AbortOnDropHandle { handle: self }
+    }
+}
+
+/// A JoinHandle that cancels its task when dropped, created with JoinHandle::abort_on_drop.
+/// It can be awaited like a JoinHandle to get the result of the task.
+pub struct AbortOnDropHandle<T> {
+    handle: JoinHandle<T>
+}
+
+impl<T> fmt::Debug for AbortOnDropHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AbortOnDropHandle").finish()
+    }
+}
+
+impl<T> AbortOnDropHandle<T> {
+    /// Returns a new AbortHandle that can be used to cancel the task independently of this handle.
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.handle.abort_handle()
+    }
+}
+
+impl<T: 'static> AbortOnDropHandle<T> {
+    /// Waits for the task to finish, returning a JoinError if the task panicked or was cancelled instead of panicking on the awaiting task.
+    pub async fn try_join(mut self) -> Result<T, JoinError> {
+        core::future::poll_fn(|cx| self.handle.poll_result(cx)).await
+    }
+}
+
+impl<T: 'static> Future for AbortOnDropHandle<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        Pin::new(&mut self.handle).poll(cx)
+    }
+}
+
+impl<T> Drop for AbortOnDropHandle<T> {
+    fn drop(&mut self) {
+        self.handle.inner.abort();
+    }
+}
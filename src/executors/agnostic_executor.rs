@@ -1,17 +1,25 @@
 use core::future::Future;
 
 use super::join_handle::*;
+use super::shutdown::{TaskTracker, ShutdownSignal};
+use super::current::{with_executor, with_executor_task};
 
-#[cfg(feature = "smol_executor")]
 use std::sync::Arc;
 
+#[cfg(feature = "simulation_executor")]
+use super::simulation_executor::SimulationExecutor;
+
 pub(crate) enum ExecutorInner {
     #[cfg(feature = "tokio_executor")]
     TokioRuntime(tokio::runtime::Runtime),
     #[cfg(feature = "async_std_executor")]
     AsyncStdRuntime,
     #[cfg(feature = "smol_executor")]
-    SmolRuntime(Arc<async_executor::Executor<'static>>, usize),
+    SmolRuntime(Arc<async_executor::Executor<'static>>, super::config::SmolThreads),
+    #[cfg(feature = "async_global_executor")]
+    AsyncGlobalRuntime,
+    #[cfg(feature = "simulation_executor")]
+    SimulationRuntime(Arc<SimulationExecutor>),
     #[cfg(feature = "futures_executor")]
     FuturesRuntime(futures::executor::ThreadPool),
     #[cfg(feature = "wasm_bindgen_executor")]
@@ -26,28 +34,88 @@ pub(crate) enum ExecutorInnerHandle {
     AsyncStdHandle,
     #[cfg(feature = "smol_executor")]
     SmolHandle(Arc<async_executor::Executor<'static>>),
+    #[cfg(feature = "async_global_executor")]
+    AsyncGlobalHandle,
+    #[cfg(feature = "simulation_executor")]
+    SimulationHandle(Arc<SimulationExecutor>),
     #[cfg(feature = "futures_executor")]
-    FuturesHandle(futures::executor::ThreadPool),
+    FuturesHandle(futures::executor::ThreadPool, super::blocking_pool::BlockingPool),
     #[cfg(feature = "wasm_bindgen_executor")]
     WasmBindgenHandle
 }
 
 use ExecutorInnerHandle::*;
 
+// A task for the thread pools run by this crate instead of the executor: the blocking pool of the futures executor, and the compute and rayon pools
+#[cfg(any(feature = "futures_executor", feature = "compute", feature = "rayon"))]
+pub(crate) type Job = Box<dyn FnOnce() + Send + 'static>;
+
 /// An executor that can spawn futures.
 /// This can be freely stored anywhere you need, cloned, and be sent to other threads.
 #[derive(Debug, Clone)]
 pub struct AgnosticExecutor {
-    pub(crate) inner: ExecutorInnerHandle
+    pub(crate) inner: ExecutorInnerHandle,
+    pub(crate) tracker: TaskTracker,
+    #[cfg(feature = "time")]
+    pub(crate) clock: Option<crate::time::VirtualClock>,
+    #[cfg(feature = "rayon")]
+    pub(crate) rayon_pool: Option<Arc<rayon::ThreadPool>>
 }
 
 impl AgnosticExecutor {
+    // An executor for a runtime that isn't owned by any manager
+    #[cfg_attr(not(any(feature = "tokio_executor", feature = "async_std_executor", feature = "smol_executor", feature = "futures_executor")), allow(dead_code))]
+    pub(crate) fn from_inner(inner: ExecutorInnerHandle) -> Self {
+        AgnosticExecutor {
+            inner,
+            tracker: TaskTracker::new(),
+            #[cfg(feature = "time")]
+            clock: None,
+            #[cfg(feature = "rayon")]
+            rayon_pool: None
+        }
+    }
+
+    /// Creates an executor that spawns on a Tokio runtime owned and driven by someone else.
+    /// It isn't part of any manager, so its tasks are not awaited on shutdown, and there is no LocalAgnosticExecutor for it.
+    #[cfg(feature = "tokio_executor")]
+    pub fn from_tokio_handle(handle: tokio::runtime::Handle) -> Self {
+        AgnosticExecutor::from_inner(TokioHandle(handle))
+    }
+
+    /// Creates an executor that spawns on a Smol executor owned and driven by someone else.
+    /// The tasks only make progress while some thread runs the executor.
+    /// It isn't part of any manager, so its tasks are not awaited on shutdown, and there is no LocalAgnosticExecutor for it.
+    #[cfg(feature = "smol_executor")]
+    pub fn from_smol_executor(executor: Arc<async_executor::Executor<'static>>) -> Self {
+        AgnosticExecutor::from_inner(SmolHandle(executor))
+    }
+
+    /// Creates an executor that spawns on a thread pool from the futures crate owned by someone else.
+    /// Blocking tasks run on a new pool owned by this crate, with the default settings.
+    /// It isn't part of any manager, so its tasks are not awaited on shutdown, and there is no LocalAgnosticExecutor for it.
+    #[cfg(feature = "futures_executor")]
+    pub fn from_futures_thread_pool(pool: futures::executor::ThreadPool) -> Self {
+        let blocking_pool = super::blocking_pool::BlockingPool::new(Default::default());
+        AgnosticExecutor::from_inner(FuturesHandle(pool, blocking_pool))
+    }
+
     /// Spawns a future on this executor.
     pub fn spawn<F, T>(&self, future: F) -> JoinHandle<T>
     where
         F: Future<Output = T> + Send + 'static,
         T: Send + 'static,
     {
+        self.spawn_task(future, None)
+    }
+
+    pub(crate) fn spawn_task<F, T>(&self, future: F, name: Option<Arc<str>>) -> JoinHandle<T>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let future = self.tracker.track(catch_unwind_future(with_executor(self, future)), name.clone());
+        let slot = future.slot();
         let inner = match &self.inner {
             #[cfg(feature = "tokio_executor")]
             TokioHandle(handle) => {
@@ -55,14 +123,26 @@ impl AgnosticExecutor {
             },
             #[cfg(feature = "async_std_executor")]
             AsyncStdHandle => {
-                JoinHandleInner::<T>::AsyncStd(Some(async_std::task::spawn(future)))
+                let handle = match name {
+                    Some(name) => async_std::task::Builder::new().name(name.to_string()).spawn(future).expect("Couldn't spawn the task"),
+                    None => async_std::task::spawn(future)
+                };
+                JoinHandleInner::<T>::AsyncStd(Some(handle))
             }, 
             #[cfg(feature = "smol_executor")]
             SmolHandle(executor) => {
                 JoinHandleInner::<T>::Smol(Some(executor.spawn(future)))
             },
+            #[cfg(feature = "async_global_executor")]
+            AsyncGlobalHandle => {
+                JoinHandleInner::<T>::AsyncGlobal(Some(async_global_executor::spawn(future)))
+            },
+            #[cfg(feature = "simulation_executor")]
+            SimulationHandle(executor) => {
+                JoinHandleInner::<T>::Simulation(Some(executor.spawn(future)))
+            },
             #[cfg(feature = "futures_executor")]
-            FuturesHandle(executor) => {
+            FuturesHandle(executor, _) => {
                 // TODO See if we can use spawn_with_handle, but maybe not a good idea if we need to introduce extra dependencies/features: https://docs.rs/futures/0.3.18/futures/task/trait.SpawnExt.html
                 use futures::future::FutureExt;
                 let (future, handle) = future.remote_handle();
@@ -78,15 +158,27 @@ impl AgnosticExecutor {
             }
         };
 
-        JoinHandle{inner}
+        JoinHandle{inner, slot}
     }
 
     /// Runs the provided closure, and when possible, it does it in a way that doesn't block concurrent tasks.
+    /// The futures executor runs it on a blocking pool owned by this crate, which rejects it if the RuntimeConfig limits the queue and it's full.
+    /// Wasm has no threads to run it, so it's always rejected and the JoinHandle returns a JoinError.
     pub fn spawn_blocking<F, T>(&self, task: F) -> JoinHandle<T>
     where
         F: FnOnce() -> T + Send + 'static,
         T: Send + 'static,
     {
+        self.spawn_blocking_task(task, None)
+    }
+
+    pub(crate) fn spawn_blocking_task<F, T>(&self, task: F, name: Option<Arc<str>>) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let task = with_executor_task(self, task);
+        let (task, slot) = self.tracker.track_blocking(catch_unwind_task(task), name);
         let inner = match &self.inner {
             #[cfg(feature = "tokio_executor")]
             TokioHandle(handle) => {
@@ -98,25 +190,96 @@ impl AgnosticExecutor {
             },
             #[cfg(feature = "smol_executor")]
             SmolHandle(executor) => {
-                JoinHandleInner::<T>::Smol(Some(executor.spawn(blocking::unblock( || task() ))))
+                JoinHandleInner::<T>::Smol(Some(executor.spawn(blocking::unblock(task))))
+            },
+            #[cfg(feature = "async_global_executor")]
+            AsyncGlobalHandle => {
+                JoinHandleInner::<T>::AsyncGlobal(Some(async_global_executor::spawn_blocking(task)))
+            },
+            #[cfg(feature = "simulation_executor")]
+            SimulationHandle(executor) => {
+                // Blocking tasks run inline on the simulation thread to keep the interleaving reproducible
+                JoinHandleInner::<T>::Simulation(Some(executor.spawn(async move { task() })))
             },
             #[cfg(feature = "futures_executor")]
-            FuturesHandle(executor) => {
-                use futures::future::FutureExt;
-                let (future, handle) = (async { task()}).remote_handle();
-                executor.spawn_ok(future); // TODO Maybe use blocking::unblock to make it use a threadpool instead of blocking the main one
-                JoinHandleInner::<T>::RemoteHandle(Some(handle))
+            FuturesHandle(_, blocking_pool) => {
+                JoinHandleInner::<T>::RemoteHandle(Some(blocking_pool.spawn(task)))
             },
             #[cfg(feature = "wasm_bindgen_executor")]
             WasmBindgenHandle => {
-                use futures::future::FutureExt;
-                let (future, handle) = (async { task() }).remote_handle();
-                wasm_bindgen_futures::spawn_local(future);
+                // There are no threads to block, and blocking the event loop would freeze the page
+                drop(task);
+                JoinHandleInner::<T>::RemoteHandle(Some(rejected_remote_handle("blocking tasks are not supported on wasm")))
+            }
+        };
+
+        JoinHandle{inner, slot}
+    }
+
+    /// Runs the provided CPU bound closure on a work-stealing pool with one thread per core, shared by every executor.
+    /// Unlike the pools of spawn_blocking, meant for blocking IO, it never grows, so heavy computations wait for a free core instead of starving the rest of the threads.
+    /// Like blocking tasks, it's only cancelled if it didn't start yet. The simulation executor runs it inline to keep the interleaving reproducible, and wasm always rejects it.
+    #[cfg(feature = "compute")]
+    pub fn spawn_compute<F, T>(&self, task: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.spawn_compute_task(task, None)
+    }
+
+    #[cfg(feature = "compute")]
+    pub(crate) fn spawn_compute_task<F, T>(&self, task: F, name: Option<Arc<str>>) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.spawn_on_thread_pool(task, name, "compute tasks are not supported on wasm", |job| super::compute_pool::ComputePool::global().spawn(job))
+    }
+
+    // Runs the task on a thread pool that isn't part of the executor, tracking it like a blocking task
+    #[cfg(any(feature = "compute", feature = "rayon"))]
+    #[cfg_attr(not(feature = "wasm_bindgen_executor"), allow(unused_variables))]
+    pub(crate) fn spawn_on_thread_pool<F, T>(&self, task: F, name: Option<Arc<str>>, rejected: &'static str, spawn: impl FnOnce(Job)) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        use futures::future::FutureExt;
+
+        let task = with_executor_task(self, task);
+        let (task, slot) = self.tracker.track_blocking(catch_unwind_task(task), name);
+        #[allow(unreachable_patterns)]
+        let inner = match &self.inner {
+            #[cfg(feature = "simulation_executor")]
+            SimulationHandle(executor) => {
+                // The simulation runs them inline to keep the interleaving reproducible
+                JoinHandleInner::<T>::Simulation(Some(executor.spawn(async move { task() })))
+            },
+            #[cfg(feature = "wasm_bindgen_executor")]
+            WasmBindgenHandle => {
+                drop(task);
+                JoinHandleInner::<T>::RemoteHandle(Some(rejected_remote_handle(rejected)))
+            },
+            _ => {
+                let (future, handle) = (async move { task() }).remote_handle();
+                spawn(Box::new(move || { future.now_or_never(); }));
                 JoinHandleInner::<T>::RemoteHandle(Some(handle))
             }
         };
 
-        JoinHandle{inner}
+        JoinHandle{inner, slot}
+    }
+
+    /// Returns a future that completes when the graceful shutdown of this executor is requested through a ShutdownHandle.
+    /// Long running tasks can use it to stop what they are doing and finish in an orderly way.
+    pub fn shutdown_signal(&self) -> ShutdownSignal {
+        self.tracker.shutdown_signal()
+    }
+
+    /// Checks if the graceful shutdown of this executor has been requested.
+    pub fn is_shutting_down(&self) -> bool {
+        self.tracker.is_shutdown()
     }
 
     // TODO spawn_local on supported platforms
@@ -141,8 +304,16 @@ impl AgnosticExecutor {
             SmolHandle(_) => {
                 futures_lite::future::block_on(future)
             },
+            #[cfg(feature = "async_global_executor")]
+            AsyncGlobalHandle => {
+                async_global_executor::block_on(future)
+            },
+            #[cfg(feature = "simulation_executor")]
+            SimulationHandle(executor) => {
+                executor.block_on(future)
+            },
             #[cfg(feature = "futures_executor")]
-            FuturesHandle(_) => {
+            FuturesHandle(..) => {
                 futures::executor::block_on(future)
             },
             #[cfg(feature = "wasm_bindgen_executor")]
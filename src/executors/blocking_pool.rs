@@ -0,0 +1,139 @@
+use core::{fmt, time::Duration};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Condvar, Mutex}
+};
+
+use futures::future::{FutureExt, RemoteHandle};
+
+use super::agnostic_executor::Job;
+use super::config::ThreadHook;
+use super::join_handle::*;
+
+/// The settings of a BlockingPool, taken from the RuntimeConfig of the executor.
+#[derive(Clone)]
+pub(crate) struct BlockingPoolConfig {
+    pub(crate) max_threads: usize,
+    pub(crate) keep_alive: Duration,
+    pub(crate) queue_limit: Option<usize>,
+    pub(crate) thread_name: String,
+    pub(crate) thread_stack_size: Option<usize>,
+    pub(crate) on_thread_start: Option<ThreadHook>,
+    pub(crate) on_thread_stop: Option<ThreadHook>
+}
+
+impl Default for BlockingPoolConfig {
+    fn default() -> Self {
+        BlockingPoolConfig {
+            max_threads: 512,
+            keep_alive: Duration::from_secs(10),
+            queue_limit: None,
+            thread_name: "agnostic-blocking".to_owned(),
+            thread_stack_size: None,
+            on_thread_start: None,
+            on_thread_stop: None
+        }
+    }
+}
+
+struct PoolState {
+    queue: VecDeque<Job>,
+    threads: usize,
+    idle: usize
+}
+
+struct PoolInner {
+    config: BlockingPoolConfig,
+    state: Mutex<PoolState>,
+    available: Condvar
+}
+
+/// A pool of threads for blocking tasks, used by the executors that don't have their own, so that blocking tasks don't starve the async ones.
+/// Threads are created on demand up to the maximum, and they stop after being idle for the keep alive time.
+#[derive(Clone)]
+pub(crate) struct BlockingPool(Arc<PoolInner>);
+
+impl fmt::Debug for BlockingPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.0.state.lock().unwrap();
+        f.debug_struct("BlockingPool")
+            .field("threads", &state.threads)
+            .field("queued", &state.queue.len())
+            .finish()
+    }
+}
+
+impl BlockingPool {
+    pub(crate) fn new(config: BlockingPoolConfig) -> Self {
+        let state = PoolState { queue: VecDeque::new(), threads: 0, idle: 0 };
+        BlockingPool(Arc::new(PoolInner { config, state: Mutex::new(state), available: Condvar::new() }))
+    }
+
+    /// Queues the task to run on the pool. If the queue is full, the task is dropped without running and the handle returns a rejected JoinError.
+    /// Only the tasks that have to wait for a thread count for the limit, the ones taken by an idle thread or by a new one don't.
+    pub(crate) fn spawn<T: Send + 'static>(&self, task: impl FnOnce() -> TaskResult<T> + Send + 'static) -> RemoteHandle<TaskResult<T>> {
+        let mut state = self.0.state.lock().unwrap();
+
+        // The queued tasks beyond the idle threads are the ones waiting, and only when no more threads can be created
+        let full = state.threads >= self.0.config.max_threads;
+        if full && self.0.config.queue_limit.is_some_and(|limit| state.queue.len() >= limit + state.idle) {
+            drop(state);
+            return rejected_remote_handle("the blocking queue is full");
+        }
+
+        let (future, handle) = (async move { task() }).remote_handle();
+        state.queue.push_back(Box::new(move || { future.now_or_never(); }));
+
+        if state.idle > 0 {
+            self.0.available.notify_one();
+        }
+        // The idle threads might not have woken up yet, so a new thread is needed if there are more queued tasks than them
+        if state.queue.len() > state.idle && state.threads < self.0.config.max_threads {
+            state.threads += 1;
+            drop(state);
+            self.start_thread();
+        }
+
+        handle
+    }
+
+    fn start_thread(&self) {
+        let pool = self.clone();
+        let mut builder = std::thread::Builder::new().name(self.0.config.thread_name.clone());
+        if let Some(stack_size) = self.0.config.thread_stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+        builder.spawn(move || pool.run_thread()).expect("Couldn't spawn a blocking thread");
+    }
+
+    fn run_thread(&self) {
+        let config = &self.0.config;
+        if let Some(hook) = &config.on_thread_start {
+            hook();
+        }
+
+        let mut state = self.0.state.lock().unwrap();
+        loop {
+            if let Some(job) = state.queue.pop_front() {
+                drop(state);
+                job();
+                state = self.0.state.lock().unwrap();
+                continue;
+            }
+
+            state.idle += 1;
+            let (next, timeout) = self.0.available.wait_timeout(state, config.keep_alive).unwrap();
+            state = next;
+            state.idle -= 1;
+            if timeout.timed_out() && state.queue.is_empty() {
+                break;
+            }
+        }
+        state.threads -= 1;
+        drop(state);
+
+        if let Some(hook) = &config.on_thread_stop {
+            hook();
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use core::{cell::RefCell, fmt, iter};
+use std::sync::{Arc, Condvar, Mutex};
+
+use crossbeam_deque::{Injector, Stealer, Worker};
+use once_cell::sync::Lazy;
+
+use super::agnostic_executor::Job;
+
+// The pool is shared by every executor, so CPU bound work never uses more threads than cores, even with several executors running
+static COMPUTE_POOL: Lazy<ComputePool> = Lazy::new(|| ComputePool::new(num_cpus::get()));
+
+thread_local! {
+    // The queue of the worker running on this thread, where the tasks spawned from a compute task go first
+    static LOCAL_QUEUE: RefCell<Option<(*const PoolInner, Worker<Job>)>> = const { RefCell::new(None) };
+}
+
+struct PoolInner {
+    injector: Injector<Job>,
+    stealers: Vec<Stealer<Job>>,
+    sleeping: Mutex<usize>,
+    wake: Condvar
+}
+
+impl PoolInner {
+    fn has_work(&self) -> bool {
+        !self.injector.is_empty() || self.stealers.iter().any(|stealer| !stealer.is_empty())
+    }
+
+    // Takes from the local queue first, then from the global one, and finally steals from the other workers
+    fn find_job(&self, local: &Worker<Job>) -> Option<Job> {
+        local.pop().or_else(|| {
+            iter::repeat_with(|| {
+                self.injector.steal_batch_and_pop(local)
+                    .or_else(|| self.stealers.iter().map(|stealer| stealer.steal()).collect())
+            })
+            .find(|steal| !steal.is_retry())
+            .and_then(|steal| steal.success())
+        })
+    }
+}
+
+/// A fixed size work-stealing pool for CPU bound tasks, with one thread per core.
+/// Unlike the blocking pools it never grows, so long computations queue instead of oversubscribing the cores.
+#[derive(Clone)]
+pub(crate) struct ComputePool(Arc<PoolInner>);
+
+impl fmt::Debug for ComputePool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComputePool").field("threads", &self.0.stealers.len()).finish()
+    }
+}
+
+impl ComputePool {
+    /// Returns the pool shared by the whole process, starting its threads the first time it's used.
+    pub(crate) fn global() -> &'static ComputePool {
+        &COMPUTE_POOL
+    }
+
+    fn new(num_threads: usize) -> Self {
+        let workers: Vec<_> = (0..num_threads).map(|_| Worker::new_lifo()).collect();
+        let stealers = workers.iter().map(|worker| worker.stealer()).collect();
+        let pool = ComputePool(Arc::new(PoolInner { injector: Injector::new(), stealers, sleeping: Mutex::new(0), wake: Condvar::new() }));
+
+        for (index, worker) in workers.into_iter().enumerate() {
+            let pool = pool.clone();
+            std::thread::Builder::new()
+                .name(format!("agnostic-compute-{}", index))
+                .spawn(move || pool.run_thread(worker))
+                .expect("Couldn't spawn a compute thread");
+        }
+        pool
+    }
+
+    /// Queues the job to run on the pool. Jobs spawned from a compute task are queued on its own thread, where they are likely to run first.
+    pub(crate) fn spawn(&self, job: Job) {
+        let job = LOCAL_QUEUE.with(|local| {
+            match &*local.borrow() {
+                Some((pool, worker)) if core::ptr::eq(*pool, Arc::as_ptr(&self.0)) => {
+                    worker.push(job);
+                    None
+                },
+                _ => Some(job)
+            }
+        });
+        if let Some(job) = job {
+            self.0.injector.push(job);
+        }
+
+        // Taking the lock makes sure a worker checking for work before sleeping sees the new job or gets the notification
+        if *self.0.sleeping.lock().unwrap() > 0 {
+            self.0.wake.notify_one();
+        }
+    }
+
+    fn run_thread(&self, worker: Worker<Job>) {
+        let inner = &*self.0;
+        LOCAL_QUEUE.with(|local| *local.borrow_mut() = Some((Arc::as_ptr(&self.0), worker)));
+
+        loop {
+            let job = LOCAL_QUEUE.with(|local| {
+                let local = local.borrow();
+                let (_, worker) = local.as_ref().unwrap();
+                inner.find_job(worker)
+            });
+
+            match job {
+                // The local queue is not borrowed while the job runs, as it might spawn other compute tasks
+                Some(job) => job(),
+                None => {
+                    let mut sleeping = inner.sleeping.lock().unwrap();
+                    if inner.has_work() {
+                        continue;
+                    }
+                    *sleeping += 1;
+                    sleeping = inner.wake.wait(sleeping).unwrap();
+                    *sleeping -= 1;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use core::{fmt, time::Duration};
+use std::sync::Arc;
+
+pub(crate) type ThreadHook = Arc<dyn Fn() + Send + Sync>;
+
+// Names the threads with the prefix followed by a number, for the executors that take a function to name their threads
+#[cfg(any(feature = "tokio_executor", feature = "async_std_executor", feature = "async_global_executor"))]
+fn numbered_thread_name(prefix: &str) -> impl Fn() -> String + Send + Sync + 'static {
+    let prefix = prefix.to_owned();
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    move || format!("{}-{}", prefix, next.fetch_add(1, std::sync::atomic::Ordering::Relaxed))
+}
+
+/// An option of a RuntimeConfig, used to report the options that an executor ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RuntimeOption {
+    /// The number of worker threads.
+    WorkerThreads,
+    /// The prefix of the names of the threads.
+    ThreadName,
+    /// The stack size of the threads.
+    ThreadStackSize,
+    /// The maximum number of threads for blocking tasks.
+    MaxBlockingThreads,
+    /// The hook called when a thread starts.
+    OnThreadStart,
+    /// The hook called when a thread stops.
+    OnThreadStop,
+    /// The time an idle blocking thread is kept alive.
+    ThreadKeepAlive,
+    /// The maximum number of blocking tasks waiting for a thread.
+    BlockingQueueLimit
+}
+
+/// A configuration for the thread pools of an executor, independent of the executor that uses it.
+///
+/// Every executor maps the options it supports to its own builder, and ignores the rest.
+/// The ignored options can be checked with AgnosticExecutorManager::ignored_options.
+/// The Async Std and async_global_executor executors share a process-wide thread pool, so their configuration only applies if it's the first one to start it.
+#[derive(Clone, Default)]
+pub struct RuntimeConfig {
+    pub(crate) worker_threads: Option<usize>,
+    pub(crate) thread_name: Option<String>,
+    pub(crate) thread_stack_size: Option<usize>,
+    pub(crate) max_blocking_threads: Option<usize>,
+    pub(crate) on_thread_start: Option<ThreadHook>,
+    pub(crate) on_thread_stop: Option<ThreadHook>,
+    pub(crate) thread_keep_alive: Option<Duration>,
+    pub(crate) blocking_queue_limit: Option<usize>
+}
+
+impl fmt::Debug for RuntimeConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RuntimeConfig")
+            .field("worker_threads", &self.worker_threads)
+            .field("thread_name", &self.thread_name)
+            .field("thread_stack_size", &self.thread_stack_size)
+            .field("max_blocking_threads", &self.max_blocking_threads)
+            .field("on_thread_start", &self.on_thread_start.is_some())
+            .field("on_thread_stop", &self.on_thread_stop.is_some())
+            .field("thread_keep_alive", &self.thread_keep_alive)
+            .field("blocking_queue_limit", &self.blocking_queue_limit)
+            .finish()
+    }
+}
+
+impl RuntimeConfig {
+    /// Creates a configuration where every option uses the default of the executor.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of worker threads that run the async tasks. It must be greater than 0.
+    pub fn worker_threads(mut self, worker_threads: usize) -> Self {
+        assert!(worker_threads > 0, "The number of worker threads must be greater than 0");
+        self.worker_threads = Some(worker_threads);
+        self
+    }
+
+    /// Sets the prefix of the names of the threads created by the executor. The executors that name each thread add its number to the prefix.
+    pub fn thread_name(mut self, thread_name: impl Into<String>) -> Self {
+        self.thread_name = Some(thread_name.into());
+        self
+    }
+
+    /// Sets the stack size in bytes of the threads created by the executor.
+    pub fn thread_stack_size(mut self, thread_stack_size: usize) -> Self {
+        self.thread_stack_size = Some(thread_stack_size);
+        self
+    }
+
+    /// Sets the maximum number of threads that run blocking tasks. It must be greater than 0.
+    pub fn max_blocking_threads(mut self, max_blocking_threads: usize) -> Self {
+        assert!(max_blocking_threads > 0, "The number of blocking threads must be greater than 0");
+        self.max_blocking_threads = Some(max_blocking_threads);
+        self
+    }
+
+    /// Sets a hook that is called on every thread created by the executor when it starts.
+    pub fn on_thread_start<F>(mut self, hook: F) -> Self where F: Fn() + Send + Sync + 'static {
+        self.on_thread_start = Some(Arc::new(hook));
+        self
+    }
+
+    /// Sets a hook that is called on every thread created by the executor before it stops.
+    pub fn on_thread_stop<F>(mut self, hook: F) -> Self where F: Fn() + Send + Sync + 'static {
+        self.on_thread_stop = Some(Arc::new(hook));
+        self
+    }
+
+    /// Sets how long an idle thread for blocking tasks is kept alive before it stops.
+    pub fn thread_keep_alive(mut self, thread_keep_alive: Duration) -> Self {
+        self.thread_keep_alive = Some(thread_keep_alive);
+        self
+    }
+
+    /// Sets the maximum number of blocking tasks waiting for a free thread. When the queue is full, new blocking tasks are rejected and their JoinHandle returns a JoinError.
+    /// Only the executors that use the blocking pool of this crate support it, the others queue every task.
+    pub fn blocking_queue_limit(mut self, blocking_queue_limit: usize) -> Self {
+        self.blocking_queue_limit = Some(blocking_queue_limit);
+        self
+    }
+
+    /// Returns the options that are set in this configuration.
+    pub(crate) fn options(&self) -> Vec<RuntimeOption> {
+        let options = [
+            (self.worker_threads.is_some(), RuntimeOption::WorkerThreads),
+            (self.thread_name.is_some(), RuntimeOption::ThreadName),
+            (self.thread_stack_size.is_some(), RuntimeOption::ThreadStackSize),
+            (self.max_blocking_threads.is_some(), RuntimeOption::MaxBlockingThreads),
+            (self.on_thread_start.is_some(), RuntimeOption::OnThreadStart),
+            (self.on_thread_stop.is_some(), RuntimeOption::OnThreadStop),
+            (self.thread_keep_alive.is_some(), RuntimeOption::ThreadKeepAlive),
+            (self.blocking_queue_limit.is_some(), RuntimeOption::BlockingQueueLimit)
+        ];
+        options.into_iter().filter(|(set, _)| *set).map(|(_, option)| option).collect()
+    }
+
+    /// Returns the options that are set in this configuration but are not in the supported ones.
+    pub(crate) fn ignored(&self, supported: &[RuntimeOption]) -> Vec<RuntimeOption> {
+        self.options().into_iter().filter(|option| !supported.contains(option)).collect()
+    }
+
+    #[cfg(feature = "tokio_executor")]
+    pub(crate) fn tokio_builder(&self, multi_thread: bool) -> (tokio::runtime::Builder, Vec<RuntimeOption>) {
+        let mut builder = if multi_thread { tokio::runtime::Builder::new_multi_thread() } else { tokio::runtime::Builder::new_current_thread() };
+        builder.enable_all();
+        if let Some(worker_threads) = self.worker_threads {
+            builder.worker_threads(worker_threads);
+        }
+        if let Some(thread_name) = &self.thread_name {
+            builder.thread_name_fn(numbered_thread_name(thread_name));
+        }
+        if let Some(thread_stack_size) = self.thread_stack_size {
+            builder.thread_stack_size(thread_stack_size);
+        }
+        if let Some(max_blocking_threads) = self.max_blocking_threads {
+            builder.max_blocking_threads(max_blocking_threads);
+        }
+        if let Some(hook) = self.on_thread_start.clone() {
+            builder.on_thread_start(move || hook());
+        }
+        if let Some(hook) = self.on_thread_stop.clone() {
+            builder.on_thread_stop(move || hook());
+        }
+        if let Some(thread_keep_alive) = self.thread_keep_alive {
+            builder.thread_keep_alive(thread_keep_alive);
+        }
+
+        // The current thread runtime only creates threads for blocking tasks
+        let mut supported = vec![
+            RuntimeOption::ThreadName, RuntimeOption::ThreadStackSize, RuntimeOption::MaxBlockingThreads,
+            RuntimeOption::OnThreadStart, RuntimeOption::OnThreadStop, RuntimeOption::ThreadKeepAlive
+        ];
+        if multi_thread {
+            supported.push(RuntimeOption::WorkerThreads);
+        }
+        (builder, self.ignored(&supported))
+    }
+
+    #[cfg(any(feature = "async_std_executor", feature = "async_global_executor"))]
+    pub(crate) fn global_executor_config(&self, env_var: Option<&'static str>) -> (async_global_executor::GlobalExecutorConfig, Vec<RuntimeOption>) {
+        let mut config = async_global_executor::GlobalExecutorConfig::default();
+        if let Some(env_var) = env_var {
+            config = config.with_env_var(env_var);
+        }
+        if let Some(worker_threads) = self.worker_threads {
+            config = config.with_min_threads(worker_threads).with_max_threads(worker_threads);
+        }
+        if let Some(thread_name) = &self.thread_name {
+            config = config.with_thread_name_fn(numbered_thread_name(thread_name));
+        }
+        (config, self.ignored(&[RuntimeOption::WorkerThreads, RuntimeOption::ThreadName]))
+    }
+
+    #[cfg(feature = "smol_executor")]
+    pub(crate) fn smol_threads(&self, num_threads: Option<usize>) -> (SmolThreads, Vec<RuntimeOption>) {
+        let threads = SmolThreads {
+            num_threads: num_threads.or(self.worker_threads).unwrap_or_else(num_cpus::get),
+            on_thread_start: self.on_thread_start.clone(),
+            on_thread_stop: self.on_thread_stop.clone()
+        };
+        // With a single thread the executor runs on the thread that starts it, so no thread is created to run the hooks
+        let supported: &[RuntimeOption] = if threads.num_threads > 1 {
+            &[RuntimeOption::WorkerThreads, RuntimeOption::OnThreadStart, RuntimeOption::OnThreadStop]
+        } else {
+            &[RuntimeOption::WorkerThreads]
+        };
+        (threads, self.ignored(supported))
+    }
+
+    #[cfg(feature = "futures_executor")]
+    pub(crate) fn futures_thread_pool(&self) -> (futures::executor::ThreadPool, Vec<RuntimeOption>) {
+        let mut builder = futures::executor::ThreadPool::builder();
+        if let Some(worker_threads) = self.worker_threads {
+            builder.pool_size(worker_threads);
+        }
+        if let Some(thread_name) = &self.thread_name {
+            builder.name_prefix(thread_name);
+        }
+        if let Some(thread_stack_size) = self.thread_stack_size {
+            builder.stack_size(thread_stack_size);
+        }
+        if let Some(hook) = self.on_thread_start.clone() {
+            builder.after_start(move |_| hook());
+        }
+        if let Some(hook) = self.on_thread_stop.clone() {
+            builder.before_stop(move |_| hook());
+        }
+        let pool = builder.create().expect("Error creating the futures threadpool");
+        (pool, self.ignored(&[
+            RuntimeOption::WorkerThreads, RuntimeOption::ThreadName, RuntimeOption::ThreadStackSize, RuntimeOption::OnThreadStart,
+            RuntimeOption::OnThreadStop, RuntimeOption::MaxBlockingThreads, RuntimeOption::ThreadKeepAlive, RuntimeOption::BlockingQueueLimit
+        ]))
+    }
+
+    // The blocking pool is owned by this crate, so it's configured even when the thread pool of the executor is provided
+    #[cfg(feature = "futures_executor")]
+    pub(crate) fn blocking_pool(&self) -> (super::blocking_pool::BlockingPool, Vec<RuntimeOption>) {
+        let mut config = super::blocking_pool::BlockingPoolConfig::default();
+        if let Some(max_blocking_threads) = self.max_blocking_threads {
+            config.max_threads = max_blocking_threads;
+        }
+        if let Some(thread_keep_alive) = self.thread_keep_alive {
+            config.keep_alive = thread_keep_alive;
+        }
+        if let Some(thread_name) = &self.thread_name {
+            config.thread_name = thread_name.clone();
+        }
+        config.queue_limit = self.blocking_queue_limit;
+        config.thread_stack_size = self.thread_stack_size;
+        config.on_thread_start = self.on_thread_start.clone();
+        config.on_thread_stop = self.on_thread_stop.clone();
+        (super::blocking_pool::BlockingPool::new(config), self.ignored(&[
+            RuntimeOption::ThreadName, RuntimeOption::ThreadStackSize, RuntimeOption::OnThreadStart,
+            RuntimeOption::OnThreadStop, RuntimeOption::MaxBlockingThreads, RuntimeOption::ThreadKeepAlive, RuntimeOption::BlockingQueueLimit
+        ]))
+    }
+}
+
+/// The worker threads of a Smol executor, that are created when it starts.
+#[cfg(feature = "smol_executor")]
+#[derive(Clone)]
+pub(crate) struct SmolThreads {
+    pub(crate) num_threads: usize,
+    on_thread_start: Option<ThreadHook>,
+    on_thread_stop: Option<ThreadHook>
+}
+
+#[cfg(feature = "smol_executor")]
+impl SmolThreads {
+    /// Runs the provided closure as the body of a worker thread, calling the hooks around it.
+    pub(crate) fn run<T>(&self, body: impl FnOnce() -> T) -> T {
+        if let Some(hook) = &self.on_thread_start {
+            hook();
+        }
+        let output = body();
+        if let Some(hook) = &self.on_thread_stop {
+            hook();
+        }
+        output
+    }
+}
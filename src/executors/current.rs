@@ -0,0 +1,118 @@
+use core::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll}
+};
+
+use std::sync::Arc;
+
+#[cfg(feature = "smol_executor")]
+use std::sync::Mutex;
+
+use super::{AgnosticExecutor, global::{self, ScopedGlobalGuard}};
+
+#[cfg(feature = "smol_executor")]
+static AMBIENT_SMOL_EXECUTOR: Mutex<Option<Arc<async_executor::Executor<'static>>>> = Mutex::new(None);
+
+thread_local! {
+    // Shared with the task, which is entered on every poll, so it's only cloned when AgnosticExecutor::current is called
+    static CURRENT_EXECUTOR: RefCell<Option<Arc<AgnosticExecutor>>> = const { RefCell::new(None) };
+}
+
+/// Returns the virtual clock of the executor driving the calling task, if any, without falling back to the ambient runtime.
+#[cfg(feature = "time")]
+pub(crate) fn current_clock() -> Option<crate::time::VirtualClock> {
+    CURRENT_EXECUTOR.with(|current| current.borrow().as_ref().and_then(|executor| executor.clock.clone()))
+}
+
+/// Sets the executor driving the code that runs on this thread until the guard is dropped.
+pub(crate) struct CurrentExecutorGuard(Option<Arc<AgnosticExecutor>>);
+
+impl CurrentExecutorGuard {
+    pub(crate) fn enter(executor: &Arc<AgnosticExecutor>) -> Self {
+        CurrentExecutorGuard(CURRENT_EXECUTOR.with(|current| current.borrow_mut().replace(executor.clone())))
+    }
+}
+
+impl Drop for CurrentExecutorGuard {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CURRENT_EXECUTOR.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Wraps a future so that AgnosticExecutor::current returns the provided executor while it's being polled.
+/// It also keeps the global executor overridden on the spawning thread, so the global functions keep using it on any thread.
+pub(crate) struct WithExecutor<F> {
+    executor: Arc<AgnosticExecutor>,
+    global: Option<Arc<AgnosticExecutor>>,
+    future: F
+}
+
+pub(crate) fn with_executor<F: Future>(executor: &AgnosticExecutor, future: F) -> WithExecutor<F> {
+    WithExecutor { executor: Arc::new(executor.clone()), global: global::overridden_global_executor(), future }
+}
+
+/// Wraps a closure like with_executor, for the tasks that run on a thread pool.
+pub(crate) fn with_executor_task<F: FnOnce() -> T, T>(executor: &AgnosticExecutor, task: F) -> impl FnOnce() -> T {
+    let executor = Arc::new(executor.clone());
+    let global = global::overridden_global_executor();
+    move || {
+        let _current = CurrentExecutorGuard::enter(&executor);
+        let _global = global.as_ref().map(ScopedGlobalGuard::enter);
+        task()
+    }
+}
+
+impl<F: Future> Future for WithExecutor<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // Safety: the future is never moved out of the struct, and the executor is not pinned
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+        let _current = CurrentExecutorGuard::enter(&this.executor);
+        let _global = this.global.as_ref().map(ScopedGlobalGuard::enter);
+        future.poll(cx)
+    }
+}
+
+impl AgnosticExecutor {
+    /// Returns the executor driving the calling task, from the root future or a task spawned by this crate.
+    /// It avoids passing the executor along to every function that needs to spawn tasks.
+    ///
+    /// Outside of the tasks spawned by this crate it falls back to the runtime detected by from_ambient, and it only returns None if there is none.
+    pub fn current() -> Option<AgnosticExecutor> {
+        CURRENT_EXECUTOR.with(|current| current.borrow().as_deref().cloned()).or_else(AgnosticExecutor::from_ambient)
+    }
+
+    /// Detects a runtime that is already running and wasn't started by this crate, and wraps it as an AgnosticExecutor.
+    /// It detects a running Tokio runtime, an Async Std task and the Smol executor registered with register_smol_executor, in that order, and only for the enabled executors.
+    /// The executor isn't part of any manager, so its tasks are not awaited on shutdown and it doesn't use a virtual clock.
+    pub fn from_ambient() -> Option<AgnosticExecutor> {
+        #[cfg(feature = "tokio_executor")]
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            return Some(AgnosticExecutor::from_tokio_handle(handle));
+        }
+
+        #[cfg(feature = "async_std_executor")]
+        if async_std::task::try_current().is_some() {
+            return Some(AgnosticExecutor::from_inner(super::ExecutorInnerHandle::AsyncStdHandle));
+        }
+
+        #[cfg(feature = "smol_executor")]
+        if let Some(executor) = AMBIENT_SMOL_EXECUTOR.lock().unwrap().clone() {
+            return Some(AgnosticExecutor::from_smol_executor(executor));
+        }
+
+        None
+    }
+
+    /// Registers the Smol executor to be detected by from_ambient, replacing the previous one. None removes the registered executor.
+    /// Smol executors cannot be detected from the running task, so applications that drive their own one need to register it for agnostic libraries to find it.
+    #[cfg(feature = "smol_executor")]
+    pub fn register_smol_executor(executor: Option<Arc<async_executor::Executor<'static>>>) {
+        *AMBIENT_SMOL_EXECUTOR.lock().unwrap() = executor;
+    }
+}
@@ -0,0 +1,82 @@
+use core::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll}
+};
+
+use std::sync::Arc;
+
+use super::AgnosticExecutor;
+
+// The executors are shared, so entering them on every poll of the tasks that inherit them doesn't clone them
+thread_local! {
+    static THREAD_GLOBAL_EXECUTOR: RefCell<Option<Arc<AgnosticExecutor>>> = const { RefCell::new(None) };
+    static SCOPED_GLOBAL_EXECUTOR: RefCell<Option<Arc<AgnosticExecutor>>> = const { RefCell::new(None) };
+}
+
+/// Returns the executor used by the global functions on this thread, if any is overridden.
+/// A scoped override takes precedence over the default of the thread.
+pub(crate) fn overridden_global_executor() -> Option<Arc<AgnosticExecutor>> {
+    SCOPED_GLOBAL_EXECUTOR.with(|scoped| scoped.borrow().clone())
+        .or_else(|| THREAD_GLOBAL_EXECUTOR.with(|default| default.borrow().clone()))
+}
+
+// Overrides the global executor of this thread until it's dropped, restoring the previous override
+pub(crate) struct ScopedGlobalGuard(Option<Arc<AgnosticExecutor>>);
+
+impl ScopedGlobalGuard {
+    pub(crate) fn enter(executor: &Arc<AgnosticExecutor>) -> Self {
+        ScopedGlobalGuard(SCOPED_GLOBAL_EXECUTOR.with(|scoped| scoped.borrow_mut().replace(executor.clone())))
+    }
+}
+
+impl Drop for ScopedGlobalGuard {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        SCOPED_GLOBAL_EXECUTOR.with(|scoped| *scoped.borrow_mut() = previous);
+    }
+}
+
+/// Runs the provided closure using the provided executor as the global executor of the current thread.
+/// The global functions current_global_executor, spawn, spawn_blocking and block_on use it instead of the process-wide global executor until the closure returns.
+/// It can be nested, and the previous global executor is restored even if the closure panics.
+/// The tasks spawned by this crate inherit the overridden global executor of the thread that spawns them, even when they run on other threads.
+pub fn with_global_executor<F, R>(executor: &AgnosticExecutor, f: F) -> R where F: FnOnce() -> R {
+    let _guard = ScopedGlobalGuard::enter(&Arc::new(executor.clone()));
+    f()
+}
+
+/// Wraps the provided future so that the provided executor is used as the global executor every time it's polled, like with_global_executor.
+/// The tasks spawned by this crate from the future inherit the override, like with with_global_executor.
+pub fn with_global_executor_async<F: Future>(executor: &AgnosticExecutor, future: F) -> WithGlobalExecutor<F> {
+    WithGlobalExecutor { executor: Arc::new(executor.clone()), future }
+}
+
+/// A future that overrides the global executor while it's being polled, created with with_global_executor_async.
+#[derive(Debug)]
+pub struct WithGlobalExecutor<F> {
+    executor: Arc<AgnosticExecutor>,
+    future: F
+}
+
+impl<F: Future> Future for WithGlobalExecutor<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // Safety: the future is never moved out of the struct, and the executor is not pinned
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+        let _guard = ScopedGlobalGuard::enter(&this.executor);
+        future.poll(cx)
+    }
+}
+
+/// Sets the default global executor of the current thread, replacing the previous one, which is returned.
+/// It's used by the global functions instead of the process-wide global executor, unless it's overridden with with_global_executor.
+/// Unlike set_as_global, it can be called many times, and None removes the default of the thread.
+/// The tasks spawned from this thread inherit it, but other threads keep their own default.
+pub fn set_thread_global_executor(executor: Option<AgnosticExecutor>) -> Option<AgnosticExecutor> {
+    let previous = THREAD_GLOBAL_EXECUTOR.with(|default| std::mem::replace(&mut *default.borrow_mut(), executor.map(Arc::new)));
+    previous.map(|previous| Arc::try_unwrap(previous).unwrap_or_else(|previous| (*previous).clone()))
+}
@@ -3,42 +3,337 @@ use core::{
     task::{Context, Poll},
     future::Future
 };
+use std::{any::Any, fmt, panic::{self, AssertUnwindSafe}, sync::Arc};
+
+use super::{TaskId, TaskSlot};
+
+/// Error returned when a spawned task didn't complete, either because it panicked, because it was cancelled or because the executor rejected it.
+pub struct JoinError {
+    repr: JoinErrorRepr,
+    task: Option<(TaskId, Option<Arc<str>>)>
+}
+
+enum JoinErrorRepr {
+    Cancelled,
+    Rejected(&'static str),
+    Taken,
+    Panic(Box<dyn Any + Send + 'static>)
+}
+
+impl JoinError {
+    pub(crate) fn cancelled() -> Self {
+        JoinError { repr: JoinErrorRepr::Cancelled, task: None }
+    }
+
+    #[cfg_attr(not(any(feature = "futures_executor", feature = "wasm_bindgen_executor")), allow(dead_code))]
+    pub(crate) fn rejected(reason: &'static str) -> Self {
+        JoinError { repr: JoinErrorRepr::Rejected(reason), task: None }
+    }
+
+    pub(crate) fn taken() -> Self {
+        JoinError { repr: JoinErrorRepr::Taken, task: None }
+    }
+
+    pub(crate) fn panic(payload: Box<dyn Any + Send + 'static>) -> Self {
+        JoinError { repr: JoinErrorRepr::Panic(payload), task: None }
+    }
+
+    pub(crate) fn with_task(mut self, slot: &TaskSlot) -> Self {
+        if self.task.is_none() {
+            self.task = Some((slot.id(), slot.name().cloned()));
+        }
+        self
+    }
+
+    // Panic payloads can't be cloned, so the copies only keep the panic message when the payload is a string, as it is for most panics
+    pub(crate) fn copy(&self) -> Self {
+        let repr = match &self.repr {
+            JoinErrorRepr::Cancelled => JoinErrorRepr::Cancelled,
+            JoinErrorRepr::Rejected(reason) => JoinErrorRepr::Rejected(reason),
+            JoinErrorRepr::Taken => JoinErrorRepr::Taken,
+            JoinErrorRepr::Panic(payload) => JoinErrorRepr::Panic(Box::new(panic_message(payload).unwrap_or("Box<dyn Any>").to_owned()))
+        };
+        JoinError { repr, task: self.task.clone() }
+    }
+
+    /// Returns the id of the task that failed, if the error comes from a spawned task.
+    pub fn id(&self) -> Option<TaskId> {
+        self.task.as_ref().map(|(id, _)| *id)
+    }
+
+    /// Returns the name of the task that failed, if it was spawned with a name.
+    pub fn task_name(&self) -> Option<&str> {
+        self.task.as_ref().and_then(|(_, name)| name.as_deref())
+    }
+
+    /// Returns true if the task was cancelled before it could complete.
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self.repr, JoinErrorRepr::Cancelled)
+    }
+
+    /// Returns true if the executor rejected the task without running it, like when its blocking queue is full or it cannot run blocking tasks.
+    pub fn is_rejected(&self) -> bool {
+        matches!(self.repr, JoinErrorRepr::Rejected(_))
+    }
+
+    /// Returns true if the result of the task was already taken with JoinHandle::try_take, so the handle has nothing left to return.
+    pub fn is_taken(&self) -> bool {
+        matches!(self.repr, JoinErrorRepr::Taken)
+    }
+
+    /// Returns true if the task panicked.
+    pub fn is_panic(&self) -> bool {
+        matches!(self.repr, JoinErrorRepr::Panic(_))
+    }
+
+    /// Consumes the error, returning the object with which the task panicked.
+    /// It panics if the error doesn't represent a panic, use try_into_panic if you are not sure.
+    pub fn into_panic(self) -> Box<dyn Any + Send + 'static> {
+        self.try_into_panic().expect("`JoinError` reason is not a panic.")
+    }
+
+    /// Consumes the error, returning the object with which the task panicked if the task panicked, or the error itself otherwise.
+    pub fn try_into_panic(self) -> Result<Box<dyn Any + Send + 'static>, JoinError> {
+        match self.repr {
+            JoinErrorRepr::Panic(payload) => Ok(payload),
+            _ => Err(self)
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> Option<&str> {
+    match (payload.downcast_ref::<&'static str>(), payload.downcast_ref::<String>()) {
+        (Some(msg), _) => Some(msg),
+        (_, Some(msg)) => Some(msg),
+        _ => None
+    }
+}
+
+impl fmt::Display for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "task")?;
+        match &self.task {
+            Some((id, Some(name))) => write!(f, " {} '{}'", id, name)?,
+            Some((id, None)) => write!(f, " {}", id)?,
+            None => {}
+        }
+        match &self.repr {
+            JoinErrorRepr::Cancelled => write!(f, " was cancelled"),
+            JoinErrorRepr::Rejected(reason) => write!(f, " was rejected: {}", reason),
+            JoinErrorRepr::Taken => write!(f, " result was already taken"),
+            JoinErrorRepr::Panic(payload) => match panic_message(&**payload) {
+                Some(msg) => write!(f, " panicked with message {:?}", msg),
+                None => write!(f, " panicked")
+            }
+        }
+    }
+}
+
+impl fmt::Debug for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.repr {
+            JoinErrorRepr::Cancelled => write!(f, "JoinError::Cancelled"),
+            JoinErrorRepr::Rejected(reason) => write!(f, "JoinError::Rejected({:?})", reason),
+            JoinErrorRepr::Taken => write!(f, "JoinError::Taken"),
+            JoinErrorRepr::Panic(_) => write!(f, "JoinError::Panic(..)")
+        }
+    }
+}
+
+impl std::error::Error for JoinError {}
+
+/// The result of a task as it's stored on every underlying handle, so that panics are reported the same way on every executor.
+pub(crate) type TaskResult<T> = Result<T, JoinError>;
+
+/// Wraps a future so that a panic while polling it is returned as a JoinError instead of unwinding into the executor.
+pub(crate) struct CatchUnwind<F> {
+    future: F
+}
+
+impl<F: Future> Future for CatchUnwind<F> {
+    type Output = TaskResult<F::Output>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // Safety: we never move the inner future, this is a plain structural projection
+        let future = unsafe { self.map_unchecked_mut(|s| &mut s.future) };
+        match panic::catch_unwind(AssertUnwindSafe(|| future.poll(cx))) {
+            Ok(Poll::Pending) => Poll::Pending,
+            Ok(Poll::Ready(res)) => Poll::Ready(Ok(res)),
+            Err(payload) => Poll::Ready(Err(JoinError::panic(payload)))
+        }
+    }
+}
+
+pub(crate) fn catch_unwind_future<F: Future>(future: F) -> CatchUnwind<F> {
+    CatchUnwind { future }
+}
+
+pub(crate) fn catch_unwind_task<F, T>(task: F) -> impl FnOnce() -> TaskResult<T>
+where
+    F: FnOnce() -> T
+{
+    move || panic::catch_unwind(AssertUnwindSafe(task)).map_err(JoinError::panic)
+}
+
+// A handle for a task that was never queued, that returns a rejected JoinError
+#[cfg(any(feature = "wasm_bindgen_executor", feature = "futures_executor"))]
+pub(crate) fn rejected_remote_handle<T>(reason: &'static str) -> futures::future::RemoteHandle<TaskResult<T>> {
+    use futures::future::FutureExt;
+    let (future, handle) = futures::future::ready(Err(JoinError::rejected(reason))).remote_handle();
+    future.now_or_never(); // It's ready, so this just sends the error to the handle
+    handle
+}
 
 pub(crate) enum JoinHandleInner<T> {
     #[cfg(feature = "tokio_executor")]
-    Tokio(Option<tokio::task::JoinHandle<T>>),
+    Tokio(Option<tokio::task::JoinHandle<TaskResult<T>>>),
     #[cfg(feature = "async_std_executor")]
-    AsyncStd(Option<async_std::task::JoinHandle<T>>),
+    AsyncStd(Option<async_std::task::JoinHandle<TaskResult<T>>>),
     #[cfg(feature = "smol_executor")]
-    Smol(Option<async_executor::Task<T>>),
-    #[cfg(any(feature = "wasm_bindgen_executor", feature = "futures_executor"))]
-    RemoteHandle(Option<futures::future::RemoteHandle<T>>)
+    Smol(Option<async_executor::Task<TaskResult<T>>>),
+    #[cfg(feature = "async_global_executor")]
+    AsyncGlobal(Option<async_global_executor::Task<TaskResult<T>>>),
+    #[cfg(feature = "simulation_executor")]
+    Simulation(Option<async_task::Task<TaskResult<T>>>),
+    #[cfg(any(feature = "wasm_bindgen_executor", feature = "futures_executor", feature = "compute", feature = "rayon"))]
+    RemoteHandle(Option<futures::future::RemoteHandle<TaskResult<T>>>)
 }
 
 impl<T> JoinHandleInner<T> {
-    async fn cancel(mut self) {
-        match &mut self {
+    async fn cancel(&mut self) {
+        match self {
             #[cfg(feature = "tokio_executor")]
-            JoinHandleInner::<T>::Tokio(handle) => { 
+            JoinHandleInner::<T>::Tokio(handle) => {
                 if let Some(handle) = handle.take() {
                     handle.abort();
                 }
             },
             #[cfg(feature = "async_std_executor")]
-            JoinHandleInner::<T>::AsyncStd(handle) => { 
+            JoinHandleInner::<T>::AsyncStd(handle) => {
                 if let Some(handle) = handle.take() {
                     handle.cancel().await;
                 }
             },
             #[cfg(feature = "smol_executor")]
             JoinHandleInner::<T>::Smol(handle) => { drop(handle.take()) },
-            #[cfg(any(feature = "wasm_bindgen_executor", feature = "futures_executor"))]
+            #[cfg(feature = "async_global_executor")]
+            JoinHandleInner::<T>::AsyncGlobal(handle) => { drop(handle.take()) },
+            #[cfg(feature = "simulation_executor")]
+            JoinHandleInner::<T>::Simulation(handle) => { drop(handle.take()) },
+            #[cfg(any(feature = "wasm_bindgen_executor", feature = "futures_executor", feature = "compute", feature = "rayon"))]
             JoinHandleInner::<T>::RemoteHandle(handle) =>  { drop(handle.take()) },
         }
         
     }
 }
 
+impl<T> JoinHandleInner<T> {
+    // Cancels the task without waiting for it, for the places that cannot await like drop
+    pub(crate) fn abort(&mut self) {
+        match self {
+            #[cfg(feature = "tokio_executor")]
+            JoinHandleInner::<T>::Tokio(handle) => {
+                if let Some(handle) = handle.take() {
+                    handle.abort();
+                }
+            },
+            #[cfg(feature = "async_std_executor")]
+            JoinHandleInner::<T>::AsyncStd(handle) => {
+                if let Some(handle) = handle.take() {
+                    // The first poll marks the task as cancelled, and dropping the future drops the task instead of detaching it
+                    let cancel = core::pin::pin!(handle.cancel());
+                    let _ = cancel.poll(&mut Context::from_waker(core::task::Waker::noop()));
+                }
+            },
+            #[cfg(feature = "smol_executor")]
+            JoinHandleInner::<T>::Smol(handle) => { drop(handle.take()) },
+            #[cfg(feature = "async_global_executor")]
+            JoinHandleInner::<T>::AsyncGlobal(handle) => { drop(handle.take()) },
+            #[cfg(feature = "simulation_executor")]
+            JoinHandleInner::<T>::Simulation(handle) => { drop(handle.take()) },
+            #[cfg(any(feature = "wasm_bindgen_executor", feature = "futures_executor", feature = "compute", feature = "rayon"))]
+            JoinHandleInner::<T>::RemoteHandle(handle) =>  { drop(handle.take()) },
+        }
+    }
+}
+
+impl<T> JoinHandleInner<T> {
+    // Uses the native completion check of the executor when available, and the completion flag of the task otherwise
+    #[cfg_attr(not(any(feature = "async_std_executor", feature = "wasm_bindgen_executor", feature = "futures_executor", feature = "compute", feature = "rayon")), allow(unused_variables))]
+    fn is_finished(&self, slot: &TaskSlot) -> bool {
+        match self {
+            #[cfg(feature = "tokio_executor")]
+            JoinHandleInner::<T>::Tokio(handle) => handle.as_ref().is_none_or(|handle| handle.is_finished()),
+            #[cfg(feature = "async_std_executor")]
+            JoinHandleInner::<T>::AsyncStd(handle) => handle.is_none() || slot.is_finished(),
+            #[cfg(feature = "smol_executor")]
+            JoinHandleInner::<T>::Smol(handle) => handle.as_ref().is_none_or(|handle| handle.is_finished()),
+            #[cfg(feature = "async_global_executor")]
+            JoinHandleInner::<T>::AsyncGlobal(handle) => handle.as_ref().is_none_or(|handle| handle.is_finished()),
+            #[cfg(feature = "simulation_executor")]
+            JoinHandleInner::<T>::Simulation(handle) => handle.as_ref().is_none_or(|handle| handle.is_finished()),
+            #[cfg(any(feature = "wasm_bindgen_executor", feature = "futures_executor", feature = "compute", feature = "rayon"))]
+            JoinHandleInner::<T>::RemoteHandle(handle) => handle.is_none() || slot.is_finished(),
+        }
+    }
+
+    // The underlying handle is released once the result has been taken
+    fn is_released(&self) -> bool {
+        match self {
+            #[cfg(feature = "tokio_executor")]
+            JoinHandleInner::<T>::Tokio(handle) => handle.is_none(),
+            #[cfg(feature = "async_std_executor")]
+            JoinHandleInner::<T>::AsyncStd(handle) => handle.is_none(),
+            #[cfg(feature = "smol_executor")]
+            JoinHandleInner::<T>::Smol(handle) => handle.is_none(),
+            #[cfg(feature = "async_global_executor")]
+            JoinHandleInner::<T>::AsyncGlobal(handle) => handle.is_none(),
+            #[cfg(feature = "simulation_executor")]
+            JoinHandleInner::<T>::Simulation(handle) => handle.is_none(),
+            #[cfg(any(feature = "wasm_bindgen_executor", feature = "futures_executor", feature = "compute", feature = "rayon"))]
+            JoinHandleInner::<T>::RemoteHandle(handle) => handle.is_none(),
+        }
+    }
+}
+
+impl<T: 'static> JoinHandleInner<T> {
+    // The handle is only None after cancel, drop or try_take, the first two consume the JoinHandle so only try_take can leave it empty
+    pub(crate) fn poll_join(&mut self, cx: &mut Context) -> Poll<TaskResult<T>> {
+        if self.is_released() {
+            return Poll::Ready(Err(JoinError::taken()));
+        }
+
+        match self {
+            #[cfg(feature = "tokio_executor")]
+            JoinHandleInner::<T>::Tokio(handle) => {
+                match futures::ready!(Pin::new(handle.as_mut().unwrap()).poll(cx)) {
+                    Ok(res) => Poll::Ready(res),
+                    Err(e) if e.is_panic() => Poll::Ready(Err(JoinError::panic(e.into_panic()))),
+                    Err(_) => Poll::Ready(Err(JoinError::cancelled()))
+                }
+            },
+            #[cfg(feature = "async_std_executor")]
+            JoinHandleInner::<T>::AsyncStd(handle) => Pin::new(handle.as_mut().unwrap()).poll(cx),
+            #[cfg(feature = "smol_executor")]
+            JoinHandleInner::<T>::Smol(handle) => Pin::new(handle.as_mut().unwrap()).poll(cx),
+            #[cfg(feature = "async_global_executor")]
+            JoinHandleInner::<T>::AsyncGlobal(handle) => Pin::new(handle.as_mut().unwrap()).poll(cx),
+            #[cfg(feature = "simulation_executor")]
+            JoinHandleInner::<T>::Simulation(handle) => Pin::new(handle.as_mut().unwrap()).poll(cx),
+            #[cfg(any(feature = "wasm_bindgen_executor", feature = "futures_executor", feature = "compute", feature = "rayon"))]
+            JoinHandleInner::<T>::RemoteHandle(handle) => {
+                // The handle resumes a Canceled panic when the executor drops the task, as the task panics are already caught any other panic is resumed
+                let handle = handle.as_mut().unwrap();
+                match panic::catch_unwind(AssertUnwindSafe(|| Pin::new(handle).poll(cx))) {
+                    Ok(res) => res,
+                    Err(e) if e.is::<futures::channel::oneshot::Canceled>() => Poll::Ready(Err(JoinError::cancelled())),
+                    Err(e) => panic::resume_unwind(e)
+                }
+            },
+        }
+    }
+}
+
 impl<T> Drop for JoinHandleInner<T> {
     fn drop(&mut self) {
         match self {
@@ -52,7 +347,19 @@ impl<T> Drop for JoinHandleInner<T> {
                     handle.detach(); // We need to detach to avoid canceling the task if we drop the handle
                 }
             },
-            #[cfg(any(feature = "wasm_bindgen_executor", feature = "futures_executor"))]
+            #[cfg(feature = "async_global_executor")]
+            JoinHandleInner::<T>::AsyncGlobal(handle) => {
+                if let Some(handle) = handle.take() {
+                    handle.detach(); // We need to detach to avoid canceling the task if we drop the handle
+                }
+            },
+            #[cfg(feature = "simulation_executor")]
+            JoinHandleInner::<T>::Simulation(handle) => {
+                if let Some(handle) = handle.take() {
+                    handle.detach(); // We need to detach to avoid canceling the task if we drop the handle
+                }
+            },
+            #[cfg(any(feature = "wasm_bindgen_executor", feature = "futures_executor", feature = "compute", feature = "rayon"))]
             JoinHandleInner::<T>::RemoteHandle(handle) =>  {
                 if let Some(handle) = handle.take() {
                     handle.forget(); // We need to forget to avoid canceling the task if we drop the handle
@@ -63,41 +370,76 @@ impl<T> Drop for JoinHandleInner<T> {
     }
 }
 
-/// A future holding the result of a spawned async task
+/// A future holding the result of a spawned async task.
+/// Awaiting it directly resumes the panic of the task if it panicked, use try_join to get a JoinError instead.
 pub struct JoinHandle<T> {
-    pub(crate) inner: JoinHandleInner<T>
+    pub(crate) inner: JoinHandleInner<T>,
+    pub(crate) slot: Arc<TaskSlot>
 }
 
 impl<T> JoinHandle<T> {
     /// Cancels the task associated with the handle. You need to await this function for the cancellation to occur.
-    pub async fn cancel(self) {
+    pub async fn cancel(mut self) {
         self.inner.cancel().await;
     }
+
+    /// Checks if the task has finished, either because it completed, panicked or was cancelled. It never blocks.
+    pub fn is_finished(&self) -> bool {
+        self.inner.is_finished(&self.slot)
+    }
 }
 
-impl<T: 'static> Future for JoinHandle<T> {
-    type Output = T;
+impl<T: 'static> JoinHandle<T> {
+    /// Waits for the task to finish, returning a JoinError if the task panicked or was cancelled instead of panicking on the awaiting task.
+    pub async fn try_join(mut self) -> Result<T, JoinError> {
+        core::future::poll_fn(|cx| self.poll_result(cx)).await
+    }
 
-    // TODO Think if we need something better than unwrap on the handle. 
-    // In theory cancel consumes the future, and is the only way to have a None there.
+    // Adds the task information to the errors of the underlying handle
+    pub(crate) fn poll_result(&mut self, cx: &mut Context) -> Poll<Result<T, JoinError>> {
+        match self.inner.poll_join(cx) {
+            Poll::Ready(res) => {
+                self.slot.release_output();
+                Poll::Ready(res.map_err(|e| e.with_task(&self.slot)))
+            },
+            Poll::Pending => Poll::Pending
+        }
+    }
 
-    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
-        let mut inner = &mut self.get_mut().inner;
-        match &mut inner {
-            #[cfg(feature = "tokio_executor")]
-            JoinHandleInner::<T>::Tokio(handle) => {
-                match futures::ready!(Pin::new(handle.as_mut().unwrap()).poll(cx)) {
-                    Ok(res) => Poll::Ready(res),
-                    Err(e) => panic!("Tokio JoinHandle error: {}", e)
-                }
+    /// Takes the result of the task if it has finished, without blocking, so it can be used from synchronous code.
+    /// It returns None while the task is running, and also once the result has been taken.
+    /// After that, try_join returns a JoinError for which is_taken is true, and awaiting the handle panics with it.
+    /// On the executors without native support the result might take a moment to be available after is_finished returns true.
+    pub fn try_take(&mut self) -> Option<Result<T, JoinError>> {
+        if self.inner.is_released() || !self.is_finished() {
+            return None;
+        }
+
+        match self.poll_result(&mut Context::from_waker(core::task::Waker::noop())) {
+            Poll::Ready(res) => {
+                self.inner.abort(); // The task already finished, it just releases the underlying handle
+                Some(res)
             },
-            #[cfg(feature = "async_std_executor")]
-            JoinHandleInner::<T>::AsyncStd(handle) => Pin::new(handle.as_mut().unwrap()).poll(cx),
-            #[cfg(feature = "smol_executor")]
-            JoinHandleInner::<T>::Smol(handle) => Pin::new(handle.as_mut().unwrap()).poll(cx),
-            #[cfg(any(feature = "wasm_bindgen_executor", feature = "futures_executor"))]
-            JoinHandleInner::<T>::RemoteHandle(handle) =>  Pin::new(handle.as_mut().unwrap()).poll(cx),
+            Poll::Pending => None
         }
     }
 }
 
+impl<T> Drop for JoinHandle<T> {
+    fn drop(&mut self) {
+        self.slot.release_output();
+    }
+}
+
+impl<T: 'static> Future for JoinHandle<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match self.get_mut().poll_result(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Ok(res)) => Poll::Ready(res),
+            Poll::Ready(Err(e)) if e.is_panic() => panic::resume_unwind(e.into_panic()),
+            Poll::Ready(Err(e)) => panic!("JoinHandle error: {}", e)
+        }
+    }
+}
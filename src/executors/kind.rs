@@ -0,0 +1,142 @@
+use core::{fmt, str::FromStr};
+
+use super::AgnosticExecutor;
+use super::ExecutorInnerHandle::*;
+
+/// The underlying executor of an AgnosticExecutor.
+/// Every kind exists regardless of the enabled features, so that it can be named in configuration, but only the enabled ones are ever returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExecutorKind {
+    /// A Tokio runtime, either multi-threaded or single-threaded.
+    Tokio,
+    /// The Async Std executor.
+    AsyncStd,
+    /// A Smol executor from the async_executor crate.
+    Smol,
+    /// The global executor from the async_global_executor crate.
+    AsyncGlobal,
+    /// The deterministic simulation executor.
+    Simulation,
+    /// A thread pool from the futures crate.
+    Futures,
+    /// The wasm executor from the wasm_bindgen_futures crate.
+    WasmBindgen
+}
+
+impl ExecutorKind {
+    /// Returns the name of the executor, the same one used by the cargo feature that enables it.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExecutorKind::Tokio => "tokio",
+            ExecutorKind::AsyncStd => "async_std",
+            ExecutorKind::Smol => "smol",
+            ExecutorKind::AsyncGlobal => "async_global",
+            ExecutorKind::Simulation => "simulation",
+            ExecutorKind::Futures => "futures",
+            ExecutorKind::WasmBindgen => "wasm_bindgen"
+        }
+    }
+}
+
+impl fmt::Display for ExecutorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ExecutorKind {
+    type Err = ExecutorKindError;
+
+    /// Parses the name of an executor, ignoring the case and accepting dashes instead of underscores, like async-std.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase().replace('-', "_");
+        match name.as_str() {
+            "tokio" => Ok(ExecutorKind::Tokio),
+            "async_std" => Ok(ExecutorKind::AsyncStd),
+            "smol" => Ok(ExecutorKind::Smol),
+            "async_global" => Ok(ExecutorKind::AsyncGlobal),
+            "simulation" => Ok(ExecutorKind::Simulation),
+            "futures" => Ok(ExecutorKind::Futures),
+            "wasm_bindgen" => Ok(ExecutorKind::WasmBindgen),
+            _ => Err(ExecutorKindError::Unknown(s.to_owned()))
+        }
+    }
+}
+
+/// The error returned when an executor cannot be selected at runtime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutorKindError {
+    /// The name doesn't match any executor.
+    Unknown(String),
+    /// The executor exists, but its feature is not enabled in this build.
+    NotEnabled(ExecutorKind),
+    /// The environment variable that selects the executor is not set.
+    NotSet
+}
+
+impl fmt::Display for ExecutorKindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecutorKindError::Unknown(name) => write!(f, "unknown executor {:?}", name),
+            ExecutorKindError::NotEnabled(kind) => write!(f, "the {} executor is not enabled, enable its cargo feature to use it", kind),
+            ExecutorKindError::NotSet => write!(f, "the {} environment variable is not set", EXECUTOR_ENV_VAR)
+        }
+    }
+}
+
+impl std::error::Error for ExecutorKindError {}
+
+/// The environment variable read by AgnosticExecutorBuilder::from_env to select the executor, like AGNOSTIC_EXECUTOR=smol.
+pub const EXECUTOR_ENV_VAR: &str = "AGNOSTIC_EXECUTOR";
+
+impl AgnosticExecutor {
+    /// Returns the kind of the underlying executor, to use executor specific features when they are needed.
+    pub fn kind(&self) -> ExecutorKind {
+        match &self.inner {
+            #[cfg(feature = "tokio_executor")]
+            TokioHandle(_) => ExecutorKind::Tokio,
+            #[cfg(feature = "async_std_executor")]
+            AsyncStdHandle => ExecutorKind::AsyncStd,
+            #[cfg(feature = "smol_executor")]
+            SmolHandle(_) => ExecutorKind::Smol,
+            #[cfg(feature = "async_global_executor")]
+            AsyncGlobalHandle => ExecutorKind::AsyncGlobal,
+            #[cfg(feature = "simulation_executor")]
+            SimulationHandle(_) => ExecutorKind::Simulation,
+            #[cfg(feature = "futures_executor")]
+            FuturesHandle(..) => ExecutorKind::Futures,
+            #[cfg(feature = "wasm_bindgen_executor")]
+            WasmBindgenHandle => ExecutorKind::WasmBindgen
+        }
+    }
+
+    /// Returns the underlying Tokio runtime handle, or None if it's another executor.
+    #[cfg(feature = "tokio_executor")]
+    #[allow(unreachable_patterns)]
+    pub fn as_tokio_handle(&self) -> Option<&tokio::runtime::Handle> {
+        match &self.inner {
+            TokioHandle(handle) => Some(handle),
+            _ => None
+        }
+    }
+
+    /// Returns the underlying Smol executor, or None if it's another executor.
+    #[cfg(feature = "smol_executor")]
+    #[allow(unreachable_patterns)]
+    pub fn as_smol_executor(&self) -> Option<&std::sync::Arc<async_executor::Executor<'static>>> {
+        match &self.inner {
+            SmolHandle(executor) => Some(executor),
+            _ => None
+        }
+    }
+
+    /// Returns the underlying thread pool from the futures crate, or None if it's another executor.
+    #[cfg(feature = "futures_executor")]
+    #[allow(unreachable_patterns)]
+    pub fn as_futures_thread_pool(&self) -> Option<&futures::executor::ThreadPool> {
+        match &self.inner {
+            FuturesHandle(pool, _) => Some(pool),
+            _ => None
+        }
+    }
+}
@@ -1,7 +1,16 @@
 use core::future::Future;
-use std::rc::Rc;
+use std::{rc::Rc, sync::Arc};
+
+#[cfg(feature = "simulation_executor")]
+use std::marker::PhantomData;
+
+#[cfg(feature = "simulation_executor")]
+use super::simulation_executor::SimulationExecutor;
 
 use super::join_handle::*;
+use super::shutdown::TaskTracker;
+use super::current::with_executor;
+use super::AgnosticExecutor;
 
 pub(crate) enum LocalExecutorInnerRuntime {
     #[cfg(feature = "tokio_executor")]
@@ -10,6 +19,10 @@ pub(crate) enum LocalExecutorInnerRuntime {
     AsyncStdRuntime,
     #[cfg(feature = "smol_executor")]
     SmolRuntime(Rc<async_executor::LocalExecutor<'static>>),
+    #[cfg(feature = "async_global_executor")]
+    AsyncGlobalRuntime,
+    #[cfg(feature = "simulation_executor")]
+    SimulationRuntime,
     #[cfg(feature = "futures_executor")]
     FuturesRuntime(futures::executor::LocalPool),
     #[cfg(feature = "wasm_bindgen_executor")]
@@ -24,6 +37,10 @@ pub(crate) enum LocalExecutorInnerHandle {
     AsyncStdHandle,
     #[cfg(feature = "smol_executor")]
     SmolHandle(Rc<async_executor::LocalExecutor<'static>>),
+    #[cfg(feature = "async_global_executor")]
+    AsyncGlobalHandle,
+    #[cfg(feature = "simulation_executor")]
+    SimulationHandle(Arc<SimulationExecutor>, PhantomData<Rc<()>>), // The marker keeps the local executor from being Send
     #[cfg(feature = "futures_executor")]
     FuturesHandle(futures::executor::LocalSpawner),
     #[cfg(feature = "wasm_bindgen_executor")]
@@ -38,7 +55,9 @@ use futures::task::LocalSpawnExt;
 /// [Send]: https://doc.rust-lang.org/std/marker/trait.Send.html
 #[derive(Debug, Clone)]
 pub struct LocalAgnosticExecutor {
-    pub(crate) inner: LocalExecutorInnerHandle
+    pub(crate) inner: LocalExecutorInnerHandle,
+    pub(crate) tracker: TaskTracker,
+    pub(crate) executor: AgnosticExecutor
 }
 
 impl LocalAgnosticExecutor {
@@ -51,6 +70,16 @@ impl LocalAgnosticExecutor {
         F: Future<Output = T> + 'static,
         T: Send + 'static,
     {
+        self.spawn_local_task(future, None)
+    }
+
+    pub(crate) fn spawn_local_task<F, T>(&self, future: F, name: Option<Arc<str>>) -> JoinHandle<T>
+    where
+        F: Future<Output = T> + 'static,
+        T: Send + 'static,
+    {
+        let future = self.tracker.track(catch_unwind_future(with_executor(&self.executor, future)), name.clone());
+        let slot = future.slot();
         let inner = match &self.inner {
             #[cfg(feature = "tokio_executor")]
             TokioHandle => {
@@ -58,12 +87,24 @@ impl LocalAgnosticExecutor {
             },
             #[cfg(feature = "async_std_executor")]
             AsyncStdHandle => {
-                JoinHandleInner::<T>::AsyncStd(Some(async_std::task::spawn_local(future)))
+                let handle = match name {
+                    Some(name) => async_std::task::Builder::new().name(name.to_string()).local(future).expect("Couldn't spawn the task"),
+                    None => async_std::task::spawn_local(future)
+                };
+                JoinHandleInner::<T>::AsyncStd(Some(handle))
             },
             #[cfg(feature = "smol_executor")]
             SmolHandle(executor) => {
                 JoinHandleInner::<T>::Smol(Some(executor.spawn(future)))
             },
+            #[cfg(feature = "async_global_executor")]
+            AsyncGlobalHandle => {
+                JoinHandleInner::<T>::AsyncGlobal(Some(async_global_executor::spawn_local(future)))
+            },
+            #[cfg(feature = "simulation_executor")]
+            SimulationHandle(executor, _) => {
+                JoinHandleInner::<T>::Simulation(Some(executor.spawn_local(future)))
+            },
             #[cfg(feature = "futures_executor")]
             FuturesHandle(executor) => {
                 use futures::future::FutureExt;
@@ -80,7 +121,7 @@ impl LocalAgnosticExecutor {
             }
         };
 
-        JoinHandle{inner}
+        JoinHandle{inner, slot}
     }
     
 }
\ No newline at end of file
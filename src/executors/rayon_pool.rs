@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use super::{AgnosticExecutor, JoinHandle};
+
+impl AgnosticExecutor {
+    /// Returns a copy of this executor whose rayon helpers use the provided pool instead of the global pool of rayon.
+    /// It's meant for the executors that are not obtained from a manager, use AgnosticExecutorManager::set_rayon_pool otherwise.
+    pub fn with_rayon_pool(mut self, pool: Arc<rayon::ThreadPool>) -> Self {
+        self.rayon_pool = Some(pool);
+        self
+    }
+
+    /// Runs the provided closure on the rayon pool of this executor, or the global pool of rayon if it doesn't have one, and returns a JoinHandle to await its result from any executor.
+    /// The closure can use the parallel iterators of rayon, which run on the same pool.
+    /// Like blocking tasks, it's only cancelled if it didn't start yet. The simulation executor runs it inline, and wasm always rejects it.
+    pub fn spawn_rayon<F, T>(&self, task: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.spawn_rayon_task(task, None)
+    }
+
+    pub(crate) fn spawn_rayon_task<F, T>(&self, task: F, name: Option<Arc<str>>) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.rayon_pool.clone();
+        self.spawn_on_thread_pool(task, name, "rayon tasks are not supported on wasm", move |job| {
+            match pool {
+                Some(pool) => pool.spawn(job),
+                None => rayon::spawn(job)
+            }
+        })
+    }
+
+    /// Maps every item in parallel with the rayon pool of this executor, returning a JoinHandle to await the results from any executor.
+    /// The results keep the order of the items for indexed collections, like vectors and ranges.
+    pub fn par_map_async<I, F, R>(&self, items: I, f: F) -> JoinHandle<Vec<R>>
+    where
+        I: IntoParallelIterator + Send + 'static,
+        F: Fn(I::Item) -> R + Send + Sync + 'static,
+        R: Send + 'static,
+    {
+        self.spawn_rayon(move || items.into_par_iter().map(f).collect())
+    }
+}
@@ -0,0 +1,98 @@
+use core::{
+    future::Future,
+    pin::Pin,
+    task::Poll
+};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use super::{AgnosticExecutor, JoinHandle};
+
+type Child<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+// The future of a child, shared between the task that polls it and the scope, which drops it if the scope is dropped first
+type SharedChild<T> = Arc<Mutex<Option<Child<'static, T>>>>;
+
+/// A scope to spawn futures that can borrow from the enclosing function, created with AgnosticExecutor::scope.
+pub struct Scope<'a, T> {
+    executor: AgnosticExecutor,
+    children: Vec<(SharedChild<T>, JoinHandle<Option<T>>)>,
+    _borrows: core::marker::PhantomData<&'a ()>
+}
+
+impl<'a, T: Send + 'static> Scope<'a, T> {
+    /// Spawns a future on the executor of the scope. It can borrow anything that outlives the scope, and its output is returned in the order the futures were spawned when the scope completes.
+    pub fn spawn<F>(&mut self, future: F) where F: Future<Output = T> + Send + 'a {
+        let child: Child<'a, T> = Box::pin(future);
+        // Safety: the scope drops the child before the borrows end, either once it's finished or when the scope is dropped
+        let child: Child<'static, T> = unsafe { core::mem::transmute(child) };
+        let shared = Arc::new(Mutex::new(Some(child)));
+
+        let task_child = shared.clone();
+        let handle = self.executor.spawn(core::future::poll_fn(move |cx| {
+            let mut child = task_child.lock().unwrap_or_else(PoisonError::into_inner);
+            let res = match child.as_mut() {
+                Some(future) => future.as_mut().poll(cx).map(Some),
+                None => return Poll::Ready(None) // Dropped by the scope
+            };
+            if res.is_ready() {
+                *child = None;
+            }
+            res
+        }));
+        self.children.push((shared, handle));
+    }
+}
+
+// Drops the children that haven't finished when the scope is dropped, waiting for the ones being polled on other threads
+struct ScopeGuard<T>(Vec<SharedChild<T>>);
+
+impl<T> Drop for ScopeGuard<T> {
+    fn drop(&mut self) {
+        for child in &self.0 {
+            child.lock().unwrap_or_else(PoisonError::into_inner).take();
+        }
+    }
+}
+
+impl AgnosticExecutor {
+    /// Creates a scope where futures that are not 'static can be spawned, and returns a future that completes when all of them have finished, with their outputs in the order they were spawned.
+    /// The children are spawned on this executor, so they run in parallel on the multi-threaded ones.
+    ///
+    /// Dropping the scope future drops every child that hasn't finished yet, blocking until the ones being polled on other threads return.
+    ///
+    /// # Safety
+    ///
+    /// The returned future must be awaited to completion or dropped, never forgotten, as the children would keep running after the borrows end.
+    /// Use scope_and_block for a safe alternative.
+    pub unsafe fn scope<'a, T, F>(&self, f: F) -> impl Future<Output = Vec<T>> + Send + 'a
+    where
+        F: FnOnce(&mut Scope<'a, T>),
+        T: Send + 'static
+    {
+        let mut scope = Scope { executor: self.clone(), children: Vec::new(), _borrows: core::marker::PhantomData };
+        f(&mut scope);
+
+        let (children, handles): (Vec<_>, Vec<_>) = scope.children.into_iter().unzip();
+        let guard = ScopeGuard(children);
+        async move {
+            let _guard = guard;
+            let mut results = Vec::with_capacity(handles.len());
+            for handle in handles {
+                results.push(handle.await.expect("The child is only dropped with the scope"));
+            }
+            results
+        }
+    }
+
+    /// Like scope, but it blocks the current thread until every child has finished, so it's safe to use.
+    /// Like block_on, it shouldn't be called from inside an async call, and it panics on wasm.
+    #[cfg(feature = "block_on")]
+    pub fn scope_and_block<'a, T, F>(&self, f: F) -> Vec<T>
+    where
+        F: FnOnce(&mut Scope<'a, T>),
+        T: Send + 'static
+    {
+        // Safety: the future is owned here, so it's either awaited to completion or dropped
+        self.block_on(unsafe { self.scope(f) })
+    }
+}
@@ -0,0 +1,153 @@
+use core::{
+    fmt,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+    future::Future
+};
+use std::{
+    panic,
+    sync::{Arc, Mutex},
+    task::Wake
+};
+
+use super::join_handle::*;
+
+// Wakes every clone waiting on the task, as the underlying handle only keeps the waker of the last poll
+struct Waiters(Mutex<Vec<Waker>>);
+
+impl Wake for Waiters {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        let wakers = std::mem::take(&mut *self.0.lock().unwrap());
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+struct SharedState<T> {
+    handle: Option<JoinHandle<T>>,
+    result: Option<Result<T, JoinError>>,
+    interested: usize,
+    cancel_requested: bool,
+    cancel_on_drop: bool
+}
+
+/// A cloneable handle to a spawned task, so that many consumers can await the same result.
+///
+/// By default the task is only cancelled when every clone still alive has requested it with cancel, dropped clones don't count.
+/// With set_cancel_on_drop, the task is also cancelled when the last clone is dropped.
+pub struct SharedJoinHandle<T> {
+    state: Arc<Mutex<SharedState<T>>>,
+    waiters: Arc<Waiters>,
+    released: bool
+}
+
+impl<T> fmt::Debug for SharedJoinHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedJoinHandle").finish()
+    }
+}
+
+impl<T> JoinHandle<T> {
+    /// Converts the handle into a SharedJoinHandle that can be cloned and awaited by many consumers.
+    pub fn shared(self) -> SharedJoinHandle<T> where T: Clone {
+        let state = SharedState { handle: Some(self), result: None, interested: 1, cancel_requested: false, cancel_on_drop: false };
+        SharedJoinHandle { state: Arc::new(Mutex::new(state)), waiters: Arc::new(Waiters(Mutex::new(Vec::new()))), released: false }
+    }
+}
+
+impl<T> SharedJoinHandle<T> {
+    /// Sets whether the task is cancelled when the last clone is dropped without getting the result. It applies to every clone.
+    pub fn set_cancel_on_drop(&self, cancel_on_drop: bool) {
+        self.state.lock().unwrap().cancel_on_drop = cancel_on_drop;
+    }
+
+    /// Requests the cancellation of the task. The task is only cancelled once every clone still alive has requested it.
+    /// You need to await this function for the cancellation to occur.
+    pub async fn cancel(mut self) {
+        self.state.lock().unwrap().cancel_requested = true;
+        if let Some(handle) = self.release() {
+            handle.cancel().await;
+        }
+    }
+
+    // Stops counting this clone as interested in the result, returning the handle of the task if it has to be cancelled
+    fn release(&mut self) -> Option<JoinHandle<T>> {
+        if self.released {
+            return None;
+        }
+        self.released = true;
+        let mut state = self.state.lock().unwrap();
+        state.interested -= 1;
+        if state.interested == 0 && (state.cancel_requested || state.cancel_on_drop) { state.handle.take() } else { None }
+    }
+}
+
+impl<T: Clone + 'static> SharedJoinHandle<T> {
+    /// Waits for the task to finish, returning a JoinError if the task panicked or was cancelled instead of panicking on the awaiting task.
+    /// Every clone gets a copy of the error, but panic payloads are only kept when they are a message.
+    pub async fn try_join(self) -> Result<T, JoinError> {
+        core::future::poll_fn(|cx| self.poll_shared(cx)).await
+    }
+
+    fn poll_shared(&self, cx: &mut Context) -> Poll<Result<T, JoinError>> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.result.is_none() {
+            {
+                let mut waiters = self.waiters.0.lock().unwrap();
+                if !waiters.iter().any(|w| w.will_wake(cx.waker())) {
+                    waiters.push(cx.waker().clone());
+                }
+            }
+            let waker = Waker::from(self.waiters.clone());
+            let res = match &mut state.handle {
+                Some(handle) => handle.poll_result(&mut Context::from_waker(&waker)),
+                None => Poll::Ready(Err(JoinError::cancelled())) // Cancelled by the other clones
+            };
+            match res {
+                Poll::Ready(res) => {
+                    state.result = Some(res);
+                    state.handle = None;
+                    self.waiters.wake_by_ref();
+                },
+                Poll::Pending => return Poll::Pending
+            }
+        }
+
+        match state.result.as_ref().unwrap() {
+            Ok(res) => Poll::Ready(Ok(res.clone())),
+            Err(e) => Poll::Ready(Err(e.copy()))
+        }
+    }
+}
+
+impl<T> Clone for SharedJoinHandle<T> {
+    fn clone(&self) -> Self {
+        self.state.lock().unwrap().interested += 1;
+        SharedJoinHandle { state: self.state.clone(), waiters: self.waiters.clone(), released: false }
+    }
+}
+
+impl<T: Clone + 'static> Future for SharedJoinHandle<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match self.poll_shared(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Ok(res)) => Poll::Ready(res),
+            Poll::Ready(Err(e)) if e.is_panic() => panic::resume_unwind(e.into_panic()),
+            Poll::Ready(Err(e)) => panic!("JoinHandle error: {}", e)
+        }
+    }
+}
+
+impl<T> Drop for SharedJoinHandle<T> {
+    fn drop(&mut self) {
+        if let Some(mut handle) = self.release() {
+            handle.inner.abort();
+        }
+    }
+}
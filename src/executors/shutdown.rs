@@ -0,0 +1,577 @@
+use core::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker}
+};
+use std::{
+    collections::HashMap,
+    task::Wake,
+    time::Duration,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering}
+    }
+};
+
+use super::join_handle::*;
+use super::task::{TaskId, TaskSlot, CurrentTaskGuard};
+
+// Marks a blocking task as finished when it runs or when the executor drops it
+struct FinishGuard(Arc<TaskSlot>);
+
+impl Drop for FinishGuard {
+    fn drop(&mut self) {
+        self.0.set_finished();
+    }
+}
+
+struct TrackerState {
+    alive: AtomicUsize,
+    blocking: AtomicUsize,
+    count_runnable: AtomicBool,
+    runnable: AtomicUsize,
+    tasks: Mutex<HashMap<TaskId, Arc<TaskSlot>>>,
+    shutdown: AtomicBool,
+    cancelled: AtomicBool,
+    shutdown_waiters: Mutex<Vec<Waker>>,
+    idle_waiters: Mutex<Vec<Waker>>,
+    blocking_waiters: Mutex<Vec<Waker>>,
+    runnable_waiters: Mutex<Vec<Waker>>,
+    report: Mutex<Option<ShutdownReport>>
+}
+
+/// Keeps track of the tasks spawned through the executors of a manager, so that they can be awaited or cancelled on shutdown.
+#[derive(Clone)]
+pub(crate) struct TaskTracker(Arc<TrackerState>);
+
+impl fmt::Debug for TaskTracker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskTracker")
+            .field("alive", &self.alive())
+            .field("shutdown", &self.is_shutdown())
+            .finish()
+    }
+}
+
+fn register(waiters: &Mutex<Vec<Waker>>, waker: &Waker) {
+    let mut waiters = waiters.lock().unwrap();
+    if !waiters.iter().any(|w| w.will_wake(waker)) {
+        waiters.push(waker.clone());
+    }
+}
+
+fn wake_all(waiters: &Mutex<Vec<Waker>>) {
+    let waiters = std::mem::take(&mut *waiters.lock().unwrap());
+    waiters.into_iter().for_each(Waker::wake);
+}
+
+impl TaskTracker {
+    pub(crate) fn new() -> Self {
+        TaskTracker(Arc::new(TrackerState {
+            alive: AtomicUsize::new(0),
+            blocking: AtomicUsize::new(0),
+            count_runnable: AtomicBool::new(false),
+            runnable: AtomicUsize::new(0),
+            tasks: Mutex::new(HashMap::new()),
+            shutdown: AtomicBool::new(false),
+            cancelled: AtomicBool::new(false),
+            shutdown_waiters: Mutex::new(Vec::new()),
+            idle_waiters: Mutex::new(Vec::new()),
+            blocking_waiters: Mutex::new(Vec::new()),
+            runnable_waiters: Mutex::new(Vec::new()),
+            report: Mutex::new(None)
+        }))
+    }
+
+    pub(crate) fn alive(&self) -> usize {
+        self.0.alive.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn is_shutdown(&self) -> bool {
+        self.0.shutdown.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn shutdown(&self) {
+        self.0.shutdown.store(true, Ordering::SeqCst);
+        wake_all(&self.0.shutdown_waiters);
+    }
+
+    /// Checks if the tasks were cancelled because the shutdown grace period expired.
+    #[cfg(feature = "tokio_executor")]
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Cancels every async task still alive, they drop their future and complete with a cancelled JoinError the next time they are polled.
+    /// Blocking tasks cannot be interrupted and keep running.
+    pub(crate) fn cancel_all(&self) {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+        let slots: Vec<_> = self.0.tasks.lock().unwrap().values().cloned().collect();
+        slots.iter().for_each(|slot| slot.wake());
+    }
+
+    fn enter(&self) -> AliveGuard {
+        self.0.alive.fetch_add(1, Ordering::SeqCst);
+        AliveGuard(self.clone())
+    }
+
+    fn enter_blocking(&self) -> BlockingGuard {
+        self.0.blocking.fetch_add(1, Ordering::SeqCst);
+        BlockingGuard(self.clone())
+    }
+
+    pub(crate) fn track<F, T>(&self, future: F, name: Option<Arc<str>>) -> Tracked<F>
+    where
+        F: Future<Output = TaskResult<T>>
+    {
+        let slot = TaskSlot::new(name);
+        self.0.tasks.lock().unwrap().insert(slot.id(), slot.clone());
+        let runnable = self.runnable_waker();
+        Tracked { future: Some(future), tracker: Some(self.clone()), slot, guard: Some(self.enter()), waker: None, runnable }
+    }
+
+    pub(crate) fn track_blocking<F, T>(&self, task: F, name: Option<Arc<str>>) -> (impl FnOnce() -> TaskResult<T>, Arc<TaskSlot>)
+    where
+        F: FnOnce() -> TaskResult<T>
+    {
+        let guard = self.enter();
+        let blocking = self.enter_blocking();
+        let slot = TaskSlot::new(name);
+        let finish = FinishGuard(slot.clone());
+        let task = move || {
+            let guard = guard;
+            let _blocking = blocking;
+            let finish = finish;
+            let _current = CurrentTaskGuard::enter(&finish.0);
+            let res = if finish.0.is_aborted() { Err(JoinError::cancelled()) } else { task() };
+            // The task stops counting as blocking before the handle is woken
+            if let Some(running) = guard.0.hold_runnable() {
+                finish.0.hold_output(running);
+            }
+            res
+        };
+        (task, slot)
+    }
+
+    pub(crate) fn set_report(&self, report: ShutdownReport) {
+        *self.0.report.lock().unwrap() = Some(report);
+    }
+
+    pub(crate) fn shutdown_signal(&self) -> ShutdownSignal {
+        ShutdownSignal { tracker: self.clone() }
+    }
+
+    pub(crate) async fn idle(&self) {
+        core::future::poll_fn(|cx| {
+            if self.alive() == 0 {
+                return Poll::Ready(());
+            }
+            register(&self.0.idle_waiters, cx.waker());
+            // Check again in case the last task finished while registering
+            if self.alive() == 0 { Poll::Ready(()) } else { Poll::Pending }
+        }).await
+    }
+
+    #[cfg(feature = "time")]
+    pub(crate) fn blocking(&self) -> usize {
+        self.0.blocking.load(Ordering::SeqCst)
+    }
+
+    /// Waits until no blocking task is queued or running. They run outside of the executor, so they can't be seen waiting or making progress.
+    #[cfg(feature = "time")]
+    pub(crate) async fn blocking_idle(&self) {
+        core::future::poll_fn(|cx| {
+            if self.blocking() == 0 {
+                return Poll::Ready(());
+            }
+            register(&self.0.blocking_waiters, cx.waker());
+            // Check again in case the last blocking task finished while registering
+            if self.blocking() == 0 { Poll::Ready(()) } else { Poll::Pending }
+        }).await
+    }
+
+    /// Starts counting the tasks that are runnable, so that runnable_idle can tell when the executor has nothing to do.
+    /// It wraps the waker of every task spawned afterwards, so it's only enabled for the executors with an auto advancing clock.
+    #[cfg(feature = "time")]
+    pub(crate) fn count_runnable(&self) {
+        self.0.count_runnable.store(true, Ordering::SeqCst);
+    }
+
+    #[cfg(feature = "time")]
+    pub(crate) fn runnable(&self) -> usize {
+        self.0.runnable.load(Ordering::SeqCst)
+    }
+
+    /// Waits until no task is woken or being polled, other than the one awaiting this.
+    #[cfg(feature = "time")]
+    pub(crate) async fn runnable_idle(&self) {
+        core::future::poll_fn(|cx| {
+            if self.runnable() == 0 {
+                return Poll::Ready(());
+            }
+            register(&self.0.runnable_waiters, cx.waker());
+            // Check again in case the last runnable task was polled while registering
+            if self.runnable() == 0 { Poll::Ready(()) } else { Poll::Pending }
+        }).await
+    }
+
+    // The waker of a new task when the runnable tasks are counted. The task is runnable until it's polled for the first time
+    pub(crate) fn runnable_waker(&self) -> Option<(Arc<RunnableWaker>, Waker)> {
+        if !self.0.count_runnable.load(Ordering::SeqCst) {
+            return None;
+        }
+        self.0.runnable.fetch_add(1, Ordering::SeqCst);
+        let runnable = Arc::new(RunnableWaker { tracker: self.clone(), state: AtomicU8::new(SCHEDULED), waker: Mutex::new(None) });
+        let waker = Waker::from(runnable.clone());
+        Some((runnable, waker))
+    }
+
+    // Counts a finished task as runnable until its handle is woken, as long as the runnable tasks are counted
+    fn hold_runnable(&self) -> Option<RunningGuard> {
+        if !self.0.count_runnable.load(Ordering::SeqCst) {
+            return None;
+        }
+        self.0.runnable.fetch_add(1, Ordering::SeqCst);
+        Some(RunningGuard(self.clone()))
+    }
+
+    fn leave_runnable(&self) {
+        if self.0.runnable.fetch_sub(1, Ordering::SeqCst) == 1 {
+            wake_all(&self.0.runnable_waiters);
+        }
+    }
+
+    /// Waits until every async task has finished or has been polled after being cancelled, so their futures have been dropped.
+    pub(crate) async fn drained(&self) {
+        core::future::poll_fn(|cx| {
+            if self.0.tasks.lock().unwrap().is_empty() {
+                return Poll::Ready(());
+            }
+            register(&self.0.idle_waiters, cx.waker());
+            if self.0.tasks.lock().unwrap().is_empty() { Poll::Ready(()) } else { Poll::Pending }
+        }).await
+    }
+}
+
+struct AliveGuard(TaskTracker);
+
+impl Drop for AliveGuard {
+    fn drop(&mut self) {
+        if self.0.0.alive.fetch_sub(1, Ordering::SeqCst) == 1 {
+            wake_all(&self.0.0.idle_waiters);
+        }
+    }
+}
+
+// Counts a blocking task as queued or running until it runs or the executor drops it
+struct BlockingGuard(TaskTracker);
+
+impl Drop for BlockingGuard {
+    fn drop(&mut self) {
+        if self.0.0.blocking.fetch_sub(1, Ordering::SeqCst) == 1 {
+            wake_all(&self.0.0.blocking_waiters);
+        }
+    }
+}
+
+const IDLE: u8 = 0;
+const SCHEDULED: u8 = 1;
+const DONE: u8 = 2;
+
+/// Wraps the waker of a task to count it as runnable from the moment it's woken until its poll returns.
+/// This is how the virtual clock knows that the executor is idle, on any number of threads.
+pub(crate) struct RunnableWaker {
+    tracker: TaskTracker,
+    state: AtomicU8,
+    waker: Mutex<Option<Waker>>
+}
+
+impl RunnableWaker {
+    /// Sets the waker of the executor, which is woken with this one.
+    pub(crate) fn set_waker(&self, waker: &Waker) {
+        *self.waker.lock().unwrap() = Some(waker.clone());
+    }
+
+    /// Counts the task as runnable until the guard is dropped, whether it was woken or not.
+    pub(crate) fn enter(&self) -> RunningGuard {
+        if self.state.compare_exchange(SCHEDULED, IDLE, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+            self.tracker.0.runnable.fetch_add(1, Ordering::SeqCst);
+        }
+        RunningGuard(self.tracker.clone())
+    }
+
+    /// Like enter, but only if the task has been woken since it was last polled.
+    #[cfg(feature = "time")]
+    pub(crate) fn try_enter(&self) -> Option<RunningGuard> {
+        self.state.compare_exchange(SCHEDULED, IDLE, Ordering::SeqCst, Ordering::SeqCst).ok()
+            .map(|_| RunningGuard(self.tracker.clone()))
+    }
+
+    // Stops counting the task once it's finished, even if it's woken again
+    fn finish(&self) {
+        if self.state.swap(DONE, Ordering::SeqCst) == SCHEDULED {
+            self.tracker.leave_runnable();
+        }
+    }
+}
+
+impl Wake for RunnableWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        if self.state.compare_exchange(IDLE, SCHEDULED, Ordering::SeqCst, Ordering::SeqCst).is_ok() {
+            self.tracker.0.runnable.fetch_add(1, Ordering::SeqCst);
+        }
+        if let Some(waker) = &*self.waker.lock().unwrap() {
+            waker.wake_by_ref();
+        }
+    }
+}
+
+pub(crate) struct RunningGuard(TaskTracker);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.leave_runnable();
+    }
+}
+
+/// Wraps a spawned future so that it's counted as alive until it finishes or is dropped, and so that it can be cancelled on shutdown or through its slot.
+pub(crate) struct Tracked<F> {
+    future: Option<F>,
+    tracker: Option<TaskTracker>,
+    slot: Arc<TaskSlot>,
+    guard: Option<AliveGuard>,
+    // The waker last stored in the slot, to avoid locking it on every poll
+    waker: Option<Waker>,
+    // The waker passed to the future when the runnable tasks are counted
+    runnable: Option<(Arc<RunnableWaker>, Waker)>
+}
+
+/// Wraps a future that isn't counted by any tracker, like the root future, so that it can still be cancelled through its slot.
+#[cfg(feature = "wasm_bindgen_executor")]
+pub(crate) fn untracked<F>(future: F) -> Tracked<F> {
+    Tracked { future: Some(future), tracker: None, slot: TaskSlot::new(None), guard: None, waker: None, runnable: None }
+}
+
+impl<F> Tracked<F> {
+    pub(crate) fn slot(&self) -> Arc<TaskSlot> {
+        self.slot.clone()
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.slot.is_aborted() || self.tracker.as_ref().is_some_and(|tracker| tracker.0.cancelled.load(Ordering::SeqCst))
+    }
+
+    fn finish(&mut self) {
+        if let Some((runnable, _)) = &self.runnable {
+            runnable.finish();
+        }
+        if self.slot.set_finished() {
+            return;
+        }
+        drop(self.guard.take());
+        if let Some(tracker) = &self.tracker {
+            let mut tasks = tracker.0.tasks.lock().unwrap();
+            tasks.remove(&self.slot.id());
+            if tasks.is_empty() {
+                drop(tasks);
+                wake_all(&tracker.0.idle_waiters);
+            }
+        }
+    }
+}
+
+// Keeps a finished task counted as runnable until its handle sees the output
+fn hold_output(tracker: &Option<TaskTracker>, slot: &TaskSlot) {
+    if let Some(running) = tracker.as_ref().and_then(TaskTracker::hold_runnable) {
+        slot.hold_output(running);
+    }
+}
+
+impl<F, T> Future for Tracked<F> where F: Future<Output = TaskResult<T>> {
+    type Output = TaskResult<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // Safety: the future is never moved out of the struct, only dropped in place, and no other field is pinned
+        let this = unsafe { self.get_unchecked_mut() };
+
+        // The slot only wakes the task to cancel it, so the waker is stored again only when the task is moved to another one.
+        // It's stored before checking the cancellation, so a cancellation requested in between still wakes the task
+        if !this.waker.as_ref().is_some_and(|waker| waker.will_wake(cx.waker())) {
+            this.waker = Some(cx.waker().clone());
+            // When the runnable tasks are counted, cancelling the task has to count it too
+            let slot_waker = match &this.runnable {
+                Some((runnable, waker)) => { runnable.set_waker(cx.waker()); waker.clone() },
+                None => cx.waker().clone()
+            };
+            *this.slot.waker.lock().unwrap() = Some(slot_waker);
+        }
+
+        if this.is_cancelled() {
+            this.future = None;
+            hold_output(&this.tracker, &this.slot);
+            this.finish();
+            return Poll::Ready(Err(JoinError::cancelled()));
+        }
+
+        let future = match &mut this.future {
+            Some(future) => unsafe { Pin::new_unchecked(future) },
+            None => return Poll::Ready(Err(JoinError::cancelled()))
+        };
+        let _current = CurrentTaskGuard::enter(&this.slot);
+        let res = match &this.runnable {
+            Some((runnable, waker)) => {
+                let _running = runnable.enter();
+                let res = future.poll(&mut Context::from_waker(waker));
+                if res.is_ready() {
+                    hold_output(&this.tracker, &this.slot);
+                }
+                res
+            },
+            None => future.poll(cx)
+        };
+        match res {
+            Poll::Ready(res) => {
+                this.finish();
+                Poll::Ready(res)
+            },
+            Poll::Pending => Poll::Pending
+        }
+    }
+}
+
+impl<F> Drop for Tracked<F> {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// A future that completes when the shutdown of the executor has been requested.
+pub struct ShutdownSignal {
+    tracker: TaskTracker
+}
+
+impl Future for ShutdownSignal {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if self.tracker.is_shutdown() {
+            return Poll::Ready(());
+        }
+        register(&self.tracker.0.shutdown_waiters, cx.waker());
+        // Check again in case the shutdown was requested while registering
+        if self.tracker.is_shutdown() { Poll::Ready(()) } else { Poll::Pending }
+    }
+}
+
+/// The outcome of a graceful shutdown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Number of spawned tasks that were still alive when the grace period expired, and had to be cancelled.
+    /// Blocking tasks are included, even if they cannot be interrupted.
+    pub still_alive: usize
+}
+
+/// A handle to request the graceful shutdown of an executor. It can be cloned and sent to any thread, for example to a signal handler.
+///
+/// When the shutdown is requested, the tasks waiting on AgnosticExecutor::shutdown_signal are woken and the executor waits for the root future and every spawned task to finish.
+/// If they don't finish in the grace period configured on the manager, the remaining tasks are cancelled and the start call returns, leaving behind the blocking tasks that are still running.
+#[derive(Debug, Clone)]
+pub struct ShutdownHandle {
+    pub(crate) tracker: TaskTracker
+}
+
+impl ShutdownHandle {
+    /// Requests the shutdown of the executor.
+    pub fn shutdown(&self) {
+        self.tracker.shutdown();
+    }
+
+    /// Checks if the shutdown has already been requested.
+    pub fn is_shutdown(&self) -> bool {
+        self.tracker.is_shutdown()
+    }
+
+    /// Returns the report of the shutdown once the executor has finished, or None if it's still running or finished without a shutdown request.
+    pub fn report(&self) -> Option<ShutdownReport> {
+        *self.tracker.0.report.lock().unwrap()
+    }
+}
+
+/// Drives the root future of an executor, and once the shutdown is requested, waits for it and for the spawned tasks during the grace period before cancelling them.
+/// Returns the output of the root future, or None if it was cancelled because the grace period expired.
+#[cfg_attr(not(feature = "time"), allow(unused_variables))]
+pub(crate) async fn supervise<F, T>(future: F, tracker: TaskTracker, grace_period: Option<(super::AgnosticExecutor, Duration)>) -> Option<T>
+where
+    F: Future<Output = T>
+{
+    let mut root = Box::pin(future);
+    let mut signal = tracker.shutdown_signal();
+    let mut output = None;
+
+    core::future::poll_fn(|cx| {
+        match root.as_mut().poll(cx) {
+            Poll::Ready(res) => { output = Some(res); Poll::Ready(()) },
+            Poll::Pending => Pin::new(&mut signal).poll(cx)
+        }
+    }).await;
+
+    if !tracker.is_shutdown() {
+        return output;
+    }
+
+    let drain = async {
+        if output.is_none() {
+            output = Some(root.await);
+        }
+        tracker.idle().await;
+    };
+
+    let expired = match grace_period {
+        #[cfg(feature = "time")]
+        Some((executor, grace_period)) => executor.timeout(grace_period, drain).await.is_err(),
+        #[cfg(not(feature = "time"))]
+        Some((_, grace_period)) => thread_timeout(grace_period, drain).await.is_err(),
+        None => { drain.await; false }
+    };
+
+    let still_alive = tracker.alive();
+    if expired {
+        tracker.cancel_all();
+        tracker.drained().await;
+    }
+    tracker.set_report(ShutdownReport { still_alive });
+    output
+}
+
+// Without the time utilities the grace period is counted by a plain thread, which wakes the shutdown when it expires.
+// The thread isn't stopped if the tasks finish first, it just finds nothing to wake.
+#[cfg(not(feature = "time"))]
+async fn thread_timeout<F: Future>(duration: Duration, future: F) -> Result<F::Output, ()> {
+    let expired = Arc::new((AtomicBool::new(false), Mutex::new(None::<Waker>)));
+    let timer = expired.clone();
+    std::thread::Builder::new()
+        .name("agnostic-shutdown-timer".to_owned())
+        .spawn(move || {
+            std::thread::sleep(duration);
+            timer.0.store(true, Ordering::SeqCst);
+            if let Some(waker) = timer.1.lock().unwrap().take() {
+                waker.wake();
+            }
+        })
+        .expect("Couldn't spawn the shutdown timer thread");
+
+    let mut future = Box::pin(future);
+    core::future::poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+        *expired.1.lock().unwrap() = Some(cx.waker().clone());
+        // Check again in case the timer expired while storing the waker
+        if expired.0.load(Ordering::SeqCst) { Poll::Ready(Err(())) } else { Poll::Pending }
+    }).await
+}
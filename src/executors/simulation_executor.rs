@@ -0,0 +1,150 @@
+use core::{
+    fmt,
+    future::Future,
+    pin::pin,
+    task::{Context, Poll, Waker}
+};
+use std::sync::{
+    Arc, Condvar, Mutex, Weak,
+    atomic::{AtomicBool, Ordering}
+};
+use std::task::Wake;
+
+use async_task::{Runnable, Task};
+
+/// A small deterministic pseudo random generator (SplitMix64), so that a seed always produces the same poll order.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+}
+
+struct SimulationState {
+    queue: Vec<Runnable>,
+    rng: SplitMix64
+}
+
+/// An executor that runs every task on the thread that drives it, picking the next task to poll at random from a seeded generator.
+/// The same seed always reproduces the same interleaving as long as the tasks are only woken by other tasks of the simulation.
+pub(crate) struct SimulationExecutor {
+    seed: u64,
+    state: Mutex<SimulationState>,
+    ready: Condvar
+}
+
+impl fmt::Debug for SimulationExecutor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SimulationExecutor").field("seed", &self.seed).finish()
+    }
+}
+
+struct RootWaker {
+    woken: AtomicBool,
+    executor: Weak<SimulationExecutor>
+}
+
+impl Wake for RootWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::SeqCst);
+        if let Some(executor) = self.executor.upgrade() {
+            // Taking the lock avoids losing the notification while the driver checks the flag
+            let _state = executor.state.lock().unwrap();
+            executor.ready.notify_all();
+        }
+    }
+}
+
+impl SimulationExecutor {
+    pub(crate) fn new(seed: u64) -> Self {
+        SimulationExecutor {
+            seed,
+            state: Mutex::new(SimulationState { queue: Vec::new(), rng: SplitMix64(seed) }),
+            ready: Condvar::new()
+        }
+    }
+
+    fn schedule_fn(self: &Arc<Self>) -> impl Fn(Runnable) + Send + Sync + 'static {
+        let executor = Arc::downgrade(self); // Weak to avoid a cycle between the executor and the queued runnables
+        move |runnable| {
+            if let Some(executor) = executor.upgrade() {
+                executor.state.lock().unwrap().queue.push(runnable);
+                executor.ready.notify_all();
+            }
+        }
+    }
+
+    pub(crate) fn spawn<F, T>(self: &Arc<Self>, future: F) -> Task<T>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (runnable, task) = async_task::spawn(future, self.schedule_fn());
+        runnable.schedule();
+        task
+    }
+
+    // async_task panics if a local runnable is run on another thread, instead of silently breaking the Send guarantees
+    pub(crate) fn spawn_local<F, T>(self: &Arc<Self>, future: F) -> Task<T>
+    where
+        F: Future<Output = T> + 'static,
+        T: 'static,
+    {
+        let (runnable, task) = async_task::spawn_local(future, self.schedule_fn());
+        runnable.schedule();
+        task
+    }
+
+    /// Drives the simulation on the current thread until the provided future completes.
+    /// The future competes with the spawned tasks in the same randomized order.
+    pub(crate) fn block_on<F: Future>(self: &Arc<Self>, future: F) -> F::Output {
+        let root = Arc::new(RootWaker { woken: AtomicBool::new(true), executor: Arc::downgrade(self) });
+        let waker = Waker::from(root.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(future);
+
+        loop {
+            let next = {
+                let mut state = self.state.lock().unwrap();
+                loop {
+                    let root_woken = root.woken.load(Ordering::SeqCst);
+                    let candidates = state.queue.len() + root_woken as usize;
+                    if candidates > 0 {
+                        let index = (state.rng.next() % candidates as u64) as usize;
+                        if index < state.queue.len() {
+                            break Some(state.queue.swap_remove(index));
+                        } else {
+                            break None;
+                        }
+                    }
+                    state = self.ready.wait(state).unwrap();
+                }
+            };
+
+            match next {
+                Some(runnable) => { runnable.run(); },
+                None => {
+                    root.woken.store(false, Ordering::SeqCst);
+                    if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                        return output;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Drops every pending task, it must be called from the thread that drove the simulation because of the local tasks.
+    pub(crate) fn clear(&self) {
+        let queue = std::mem::take(&mut self.state.lock().unwrap().queue);
+        drop(queue);
+    }
+}
@@ -0,0 +1,231 @@
+use core::{
+    cell::RefCell,
+    fmt,
+    future::Future,
+    task::Waker
+};
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, AtomicU64, Ordering}
+};
+
+use super::{AgnosticExecutor, LocalAgnosticExecutor, JoinHandle, shutdown::RunningGuard};
+
+/// An identifier of a spawned task, unique among all the tasks spawned in the process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TaskId(u64);
+
+impl fmt::Display for TaskId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+static NEXT_TASK_ID: AtomicU64 = AtomicU64::new(1);
+
+/// The state of a single spawned task, shared between the task and its handles.
+/// It's also the registry of the task names, as only async std supports them natively.
+pub(crate) struct TaskSlot {
+    id: TaskId,
+    name: Option<Arc<str>>,
+    pub(crate) waker: Mutex<Option<Waker>>,
+    aborted: AtomicBool,
+    finished: AtomicBool,
+    output: Mutex<Output>
+}
+
+// Keeps a finished task counted as runnable until its handle sees the output, as the executor wakes the handle after the task returns
+enum Output {
+    Waiting,
+    Held(#[allow(dead_code)] RunningGuard),
+    Released
+}
+
+impl TaskSlot {
+    pub(crate) fn new(name: Option<Arc<str>>) -> Arc<Self> {
+        let id = TaskId(NEXT_TASK_ID.fetch_add(1, Ordering::Relaxed));
+        Arc::new(TaskSlot { id, name, waker: Mutex::new(None), aborted: AtomicBool::new(false), finished: AtomicBool::new(false), output: Mutex::new(Output::Waiting) })
+    }
+
+    pub(crate) fn id(&self) -> TaskId {
+        self.id
+    }
+
+    pub(crate) fn name(&self) -> Option<&Arc<str>> {
+        self.name.as_ref()
+    }
+
+    /// Cancels the task, it drops its future and completes with a cancelled JoinError the next time it's polled.
+    /// Blocking tasks are only cancelled if they didn't start yet.
+    pub(crate) fn abort(&self) {
+        self.aborted.store(true, Ordering::SeqCst);
+        self.wake();
+    }
+
+    pub(crate) fn is_aborted(&self) -> bool {
+        self.aborted.load(Ordering::SeqCst)
+    }
+
+    /// Checks if the task has completed, was cancelled or was dropped by the executor.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::SeqCst)
+    }
+
+    /// Marks the task as finished, returning true if it already was.
+    pub(crate) fn set_finished(&self) -> bool {
+        self.finished.swap(true, Ordering::SeqCst)
+    }
+
+    /// Keeps the guard until the output is released, unless the handle was already dropped.
+    pub(crate) fn hold_output(&self, guard: RunningGuard) {
+        let mut output = self.output.lock().unwrap();
+        if let Output::Waiting = *output {
+            *output = Output::Held(guard);
+        }
+    }
+
+    /// Called by the handle once it has seen the output or when it's dropped.
+    pub(crate) fn release_output(&self) {
+        let output = std::mem::replace(&mut *self.output.lock().unwrap(), Output::Released);
+        drop(output);
+    }
+
+    pub(crate) fn wake(&self) {
+        if let Some(waker) = self.waker.lock().unwrap().take() {
+            waker.wake();
+        }
+    }
+}
+
+thread_local! {
+    static CURRENT_TASK: RefCell<Option<Arc<TaskSlot>>> = const { RefCell::new(None) };
+}
+
+/// Sets the task being run on this thread until the guard is dropped.
+pub(crate) struct CurrentTaskGuard(Option<Arc<TaskSlot>>);
+
+impl CurrentTaskGuard {
+    pub(crate) fn enter(slot: &Arc<TaskSlot>) -> Self {
+        CurrentTaskGuard(CURRENT_TASK.with(|current| current.borrow_mut().replace(slot.clone())))
+    }
+}
+
+impl Drop for CurrentTaskGuard {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CURRENT_TASK.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Returns the id of the task running on the current thread, or None if it's not called from a task spawned by this crate.
+pub fn current_task_id() -> Option<TaskId> {
+    CURRENT_TASK.with(|current| current.borrow().as_ref().map(|slot| slot.id()))
+}
+
+/// Returns the name of the task running on the current thread, or None if it's not called from a named task spawned by this crate.
+pub fn current_task_name() -> Option<String> {
+    CURRENT_TASK.with(|current| current.borrow().as_ref().and_then(|slot| slot.name().map(|name| name.to_string())))
+}
+
+impl<T> JoinHandle<T> {
+    /// Returns the id of the task.
+    pub fn id(&self) -> TaskId {
+        self.slot.id()
+    }
+
+    /// Returns the name of the task, if it was spawned with a name.
+    pub fn name(&self) -> Option<&str> {
+        self.slot.name().map(|name| &**name)
+    }
+}
+
+/// A builder to configure a task before spawning it, obtained from AgnosticExecutor::task.
+#[derive(Debug)]
+pub struct TaskBuilder<'a> {
+    executor: &'a AgnosticExecutor,
+    name: Option<Arc<str>>
+}
+
+impl AgnosticExecutor {
+    /// Returns a builder to configure a new task before spawning it.
+    pub fn task(&self) -> TaskBuilder<'_> {
+        TaskBuilder { executor: self, name: None }
+    }
+}
+
+impl<'a> TaskBuilder<'a> {
+    /// Sets the name of the task. It's available from current_task_name inside the task, on its JoinHandle and on the JoinError if it fails.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into().into());
+        self
+    }
+
+    /// Spawns a future on the executor with the configuration of the builder.
+    pub fn spawn<F, T>(self, future: F) -> JoinHandle<T>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.executor.spawn_task(future, self.name)
+    }
+
+    /// Runs the provided closure with the configuration of the builder, and when possible, it does it in a way that doesn't block concurrent tasks.
+    pub fn spawn_blocking<F, T>(self, task: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.executor.spawn_blocking_task(task, self.name)
+    }
+
+    /// Runs the provided CPU bound closure with the configuration of the builder on the compute pool, like AgnosticExecutor::spawn_compute.
+    #[cfg(feature = "compute")]
+    pub fn spawn_compute<F, T>(self, task: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.executor.spawn_compute_task(task, self.name)
+    }
+
+    /// Runs the provided closure with the configuration of the builder on the rayon pool of the executor, like AgnosticExecutor::spawn_rayon.
+    #[cfg(feature = "rayon")]
+    pub fn spawn_rayon<F, T>(self, task: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.executor.spawn_rayon_task(task, self.name)
+    }
+}
+
+/// A builder to configure a local task before spawning it, obtained from LocalAgnosticExecutor::task.
+#[derive(Debug)]
+pub struct LocalTaskBuilder<'a> {
+    executor: &'a LocalAgnosticExecutor,
+    name: Option<Arc<str>>
+}
+
+impl LocalAgnosticExecutor {
+    /// Returns a builder to configure a new local task before spawning it.
+    pub fn task(&self) -> LocalTaskBuilder<'_> {
+        LocalTaskBuilder { executor: self, name: None }
+    }
+}
+
+impl<'a> LocalTaskBuilder<'a> {
+    /// Sets the name of the task. It's available from current_task_name inside the task, on its JoinHandle and on the JoinError if it fails.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into().into());
+        self
+    }
+
+    /// Spawns a future that doesn't implement Send with the configuration of the builder.
+    pub fn spawn_local<F, T>(self, future: F) -> JoinHandle<T>
+    where
+        F: Future<Output = T> + 'static,
+        T: Send + 'static,
+    {
+        self.executor.spawn_local_task(future, self.name)
+    }
+}
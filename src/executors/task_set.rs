@@ -0,0 +1,141 @@
+use core::{
+    fmt,
+    future::Future,
+    task::{Context, Poll, Waker}
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    task::Wake
+};
+
+use super::{AgnosticExecutor, JoinHandle, JoinError, TaskId};
+
+// The tasks woken since the set was last polled, so join_next only polls those instead of every task
+#[derive(Default)]
+struct ReadyQueue {
+    tasks: Mutex<Vec<TaskId>>,
+    waker: Mutex<Option<Waker>>
+}
+
+// The waker passed to the handle of a task, it queues the task and wakes the one awaiting the set
+struct TaskWaker {
+    id: TaskId,
+    ready: Arc<ReadyQueue>
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.ready.tasks.lock().unwrap().push(self.id);
+        if let Some(waker) = &*self.ready.waker.lock().unwrap() {
+            waker.wake_by_ref();
+        }
+    }
+}
+
+/// A group of spawned tasks that can be awaited in completion order.
+///
+/// Every task still in the set is cancelled when the set is dropped, so the lifetime of the tasks is bound to the owner of the set.
+/// On async std the cancellation of a dropped set is only requested, and it completes the next time the executor runs the tasks.
+pub struct TaskSet<T> {
+    executor: AgnosticExecutor,
+    handles: HashMap<TaskId, (JoinHandle<T>, Waker)>,
+    ready: Arc<ReadyQueue>
+}
+
+impl<T> fmt::Debug for TaskSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskSet").field("len", &self.handles.len()).finish()
+    }
+}
+
+impl<T> TaskSet<T> {
+    /// Creates an empty set that spawns its tasks on the provided executor.
+    pub fn new(executor: &AgnosticExecutor) -> Self {
+        TaskSet { executor: executor.clone(), handles: HashMap::new(), ready: Arc::default() }
+    }
+
+    /// Adds a task that was already spawned to the set.
+    pub fn push(&mut self, handle: JoinHandle<T>) {
+        let id = handle.id();
+        let waker = Waker::from(Arc::new(TaskWaker { id, ready: self.ready.clone() }));
+        self.handles.insert(id, (handle, waker));
+        // It's polled once to register its waker
+        self.ready.tasks.lock().unwrap().push(id);
+    }
+
+    /// Returns the number of tasks in the set that haven't been joined yet.
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    /// Checks if there are no tasks left in the set.
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    /// Cancels every task in the set and removes them from it.
+    pub fn abort_all(&mut self) {
+        for (_, (mut handle, _)) in self.handles.drain() {
+            handle.inner.abort();
+        }
+        self.ready.tasks.lock().unwrap().clear();
+    }
+}
+
+impl<T: Send + 'static> TaskSet<T> {
+    /// Spawns a new future on the executor of the set and adds it to the set.
+    pub fn spawn<F>(&mut self, future: F) where F: Future<Output = T> + Send + 'static {
+        let handle = self.executor.spawn(future);
+        self.push(handle);
+    }
+
+    /// Spawns a blocking closure on the executor of the set and adds it to the set.
+    pub fn spawn_blocking<F>(&mut self, task: F) where F: FnOnce() -> T + Send + 'static {
+        let handle = self.executor.spawn_blocking(task);
+        self.push(handle);
+    }
+}
+
+impl<T: 'static> TaskSet<T> {
+    /// Waits for the next task of the set to finish and returns its result, or None if the set is empty.
+    /// The results are returned in completion order, and a task that panicked or was cancelled returns a JoinError.
+    pub async fn join_next(&mut self) -> Option<Result<T, JoinError>> {
+        if self.handles.is_empty() {
+            return None;
+        }
+
+        core::future::poll_fn(|cx| {
+            // The waker is set before taking the woken tasks, so a task woken afterwards wakes this one again
+            {
+                let mut waker = self.ready.waker.lock().unwrap();
+                if !waker.as_ref().is_some_and(|waker| waker.will_wake(cx.waker())) {
+                    *waker = Some(cx.waker().clone());
+                }
+            }
+
+            let woken = std::mem::take(&mut *self.ready.tasks.lock().unwrap());
+            for (index, id) in woken.iter().enumerate() {
+                // A task can be woken more than once, or after it was joined
+                let Some((handle, waker)) = self.handles.get_mut(id) else { continue };
+                if let Poll::Ready(res) = handle.poll_result(&mut Context::from_waker(waker)) {
+                    self.handles.remove(id);
+                    // The rest of the woken tasks are polled on the next call
+                    self.ready.tasks.lock().unwrap().extend_from_slice(&woken[index + 1..]);
+                    return Poll::Ready(Some(res));
+                }
+            }
+            Poll::Pending
+        }).await
+    }
+}
+
+impl<T> Drop for TaskSet<T> {
+    fn drop(&mut self) {
+        self.abort_all();
+    }
+}
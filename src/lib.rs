@@ -69,16 +69,27 @@
 
 mod executors;
 
+#[allow(deprecated)]
+pub use executors::get_global_executor;
+
 pub use executors::{
-    JoinHandle, AgnosticExecutor, LocalAgnosticExecutor, AgnosticExecutorBuilder, AgnosticExecutorManager,
-    new_agnostic_executor, check_global_executor, get_global_executor, spawn, spawn_blocking
+    JoinHandle, JoinError, AbortHandle, AbortOnDropHandle, SharedJoinHandle, TaskSet, AgnosticExecutor, LocalAgnosticExecutor, AgnosticExecutorBuilder, AgnosticExecutorManager,
+    ShutdownHandle, ShutdownReport, ShutdownSignal,
+    ExecutorKind, ExecutorKindError, EXECUTOR_ENV_VAR, RuntimeConfig, RuntimeOption, TaskId, TaskBuilder, LocalTaskBuilder, current_task_id, current_task_name,
+    new_agnostic_executor, check_global_executor, current_global_executor, spawn, spawn_blocking,
+    with_global_executor, with_global_executor_async, set_thread_global_executor, WithGlobalExecutor
 };
 
 #[cfg(feature = "block_on")]
 pub use executors::block_on;
 
+#[cfg(feature = "scoped")]
+pub use executors::Scope;
+
 #[ cfg(feature = "time") ]
 pub mod time;
 
+pub mod task_local;
+
 #[ cfg(feature = "test") ]
 pub mod test;
\ No newline at end of file
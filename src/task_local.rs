@@ -0,0 +1,161 @@
+//! Task-local storage that works the same with every executor, including wasm.
+//!
+//! Values are set for the duration of a future with LocalKey::scope, and they are available to everything that future runs, but not to the tasks it spawns.
+//! To propagate a value to a spawned task, read it and wrap the spawned future in its own scope.
+//!
+//! The executor is chosen at runtime, so instead of mapping to the task locals of each executor, the value is swapped into a thread local every time the scoped future is polled.
+
+use core::{
+    cell::RefCell,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll}
+};
+
+/// Declares one or more task-local keys of type LocalKey.
+///
+/// ```
+/// agnostic_async_executor::task_local! {
+///     static REQUEST_ID: u64;
+///     pub static TENANT: String;
+/// }
+///
+/// async fn handle_request() -> u64 {
+///     REQUEST_ID.scope(42, async { REQUEST_ID.get() }).await
+/// }
+/// ```
+#[macro_export]
+macro_rules! task_local {
+    () => {};
+
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty; $($rest:tt)*) => {
+        $crate::__task_local_inner!($(#[$attr])* $vis $name, $t);
+        $crate::task_local!($($rest)*);
+    };
+
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty) => {
+        $crate::__task_local_inner!($(#[$attr])* $vis $name, $t);
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __task_local_inner {
+    ($(#[$attr:meta])* $vis:vis $name:ident, $t:ty) => {
+        $(#[$attr])*
+        $vis static $name: $crate::task_local::LocalKey<$t> = {
+            std::thread_local! {
+                static __KEY: std::cell::RefCell<Option<$t>> = const { std::cell::RefCell::new(None) };
+            }
+            $crate::task_local::LocalKey { inner: __KEY }
+        };
+    };
+}
+
+/// A key for task-local data, declared with the task_local macro.
+pub struct LocalKey<T: 'static> {
+    /// The thread local that holds the value while a scoped future is being polled. Use the task_local macro instead of creating it directly.
+    #[doc(hidden)]
+    pub inner: std::thread::LocalKey<RefCell<Option<T>>>
+}
+
+impl<T: 'static> fmt::Debug for LocalKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalKey").finish()
+    }
+}
+
+/// The error returned when accessing a task-local value outside of a scope that sets it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessError;
+
+impl fmt::Display for AccessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "task-local value not set")
+    }
+}
+
+impl std::error::Error for AccessError {}
+
+// Puts the value of the scope in the thread local, and takes it back when dropped, even if the future panics
+struct ScopeGuard<'a, T: 'static> {
+    key: &'static LocalKey<T>,
+    slot: &'a mut Option<T>
+}
+
+impl<'a, T: 'static> ScopeGuard<'a, T> {
+    fn enter(key: &'static LocalKey<T>, slot: &'a mut Option<T>) -> Self {
+        key.inner.with(|inner| std::mem::swap(&mut *inner.borrow_mut(), slot));
+        ScopeGuard { key, slot }
+    }
+}
+
+impl<T: 'static> Drop for ScopeGuard<'_, T> {
+    fn drop(&mut self) {
+        self.key.inner.with(|inner| std::mem::swap(&mut *inner.borrow_mut(), self.slot));
+    }
+}
+
+impl<T: 'static> LocalKey<T> {
+    /// Sets the value of the key while the provided future runs, nested scopes override it until they finish.
+    pub fn scope<F: Future>(&'static self, value: T, future: F) -> TaskLocalFuture<T, F> {
+        TaskLocalFuture { key: self, slot: Some(value), future }
+    }
+
+    /// Sets the value of the key while the provided closure runs.
+    pub fn sync_scope<F, R>(&'static self, value: T, f: F) -> R where F: FnOnce() -> R {
+        let mut slot = Some(value);
+        let _guard = ScopeGuard::enter(self, &mut slot);
+        f()
+    }
+
+    /// Gives access to the value of the key in the current scope.
+    /// It panics if it's called outside of a scope that sets it, use try_with if you are not sure.
+    pub fn with<F, R>(&'static self, f: F) -> R where F: FnOnce(&T) -> R {
+        self.try_with(f).expect("Cannot access a task-local value outside of its scope")
+    }
+
+    /// Gives access to the value of the key in the current scope, or returns an AccessError if it's not set.
+    pub fn try_with<F, R>(&'static self, f: F) -> Result<R, AccessError> where F: FnOnce(&T) -> R {
+        self.inner.with(|inner| {
+            match inner.borrow().as_ref() {
+                Some(value) => Ok(f(value)),
+                None => Err(AccessError)
+            }
+        })
+    }
+}
+
+impl<T: Clone + 'static> LocalKey<T> {
+    /// Returns a copy of the value of the key in the current scope.
+    /// It panics if it's called outside of a scope that sets it.
+    pub fn get(&'static self) -> T {
+        self.with(|value| value.clone())
+    }
+}
+
+/// A future that sets a task-local value while it's being polled, created with LocalKey::scope.
+pub struct TaskLocalFuture<T: 'static, F> {
+    key: &'static LocalKey<T>,
+    slot: Option<T>,
+    future: F
+}
+
+impl<T: 'static, F> fmt::Debug for TaskLocalFuture<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskLocalFuture").finish()
+    }
+}
+
+impl<T: 'static, F: Future> Future for TaskLocalFuture<T, F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // Safety: the future is never moved out of the struct, and no other field is pinned
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+        let _guard = ScopeGuard::enter(this.key, &mut this.slot);
+        future.poll(cx)
+    }
+}
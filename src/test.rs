@@ -4,9 +4,18 @@
 
 use std::sync::Arc;
 
+#[ cfg(not(feature = "wasm_bindgen_executor")) ]
+use std::ops::Range;
+
 use concurrent_queue::ConcurrentQueue;
 
-use crate::{AgnosticExecutorManager, new_agnostic_executor, check_global_executor};
+use crate::{AgnosticExecutorManager, new_agnostic_executor};
+
+#[ cfg(not(feature = "wasm_bindgen_executor")) ]
+use crate::with_global_executor;
+
+#[ cfg(feature = "wasm_bindgen_executor") ]
+use crate::check_global_executor;
 
 pub use super::{check, check_eq, check_op, check_gt, check_lt, check_ge, check_le}; // Because it's exported at the crate level, re-export  it here for convenience
 
@@ -27,11 +36,17 @@ pub struct TestHelper {
 impl TestHelper {
     
     #[ cfg(not(feature = "wasm_bindgen_executor")) ]
-    fn test_wrapper_native<F>(runtime_name: String, manager: AgnosticExecutorManager, errors: &mut Vec<String>, body: &F) where F: Fn(AgnosticExecutorManager, TestHelper) {
+    fn test_wrapper_native<F>(runtime_name: String, global: bool, manager: AgnosticExecutorManager, errors: &mut Vec<String>, body: &F) where F: Fn(AgnosticExecutorManager, TestHelper) {
         let test_queue = Arc::new(ConcurrentQueue::unbounded());
         let helper = TestHelper {runtime_name, test_queue: test_queue.clone()};
 
-        body(manager, helper);
+        if global {
+            // The global executor is overridden on the test thread, and the tasks spawned from it inherit the override
+            let executor = manager.get_executor();
+            with_global_executor(&executor, || body(manager, helper));
+        } else {
+            body(manager, helper);
+        }
 
         // IMPORTANT This assumes that manager.start is a blocking call on native platforms (unlike wasm)
 
@@ -60,42 +75,57 @@ impl TestHelper {
 }
 
 /// Define and run a native test that will be executed on all the configured executors except wasm, that needs it's own test
+/// If global is true, each executor is used as the global executor of the test thread while the test runs, and of the tasks spawned by it.
 #[ cfg(not(feature = "wasm_bindgen_executor")) ]
 pub fn test_in_native<F>(global: bool, body: F) where F: Fn(AgnosticExecutorManager, TestHelper) {
+    test_in_native_with_seeds(global, 0..1, body);
+}
+
+/// Define and run a native test like test_in_native, but running it on the simulation executor once for every seed in the range.
+/// The seed is included in the runtime name of the failed checks, so that the failing interleaving can be reproduced.
+#[ cfg(not(feature = "wasm_bindgen_executor")) ]
+#[ cfg_attr(not(feature = "simulation_executor"), allow(unused_variables)) ]
+pub fn test_in_native_with_seeds<F>(global: bool, seeds: Range<u64>, body: F) where F: Fn(AgnosticExecutorManager, TestHelper) {
     let mut errors = Vec::new();
 
-    // As we can only have one global executor, we only test tokio that is the one that has more restrictions and it's the first
+    #[ cfg(feature = "tokio_executor") ]
+    {
+        let manager = new_agnostic_executor().use_tokio_executor();
+        TestHelper::test_wrapper_native("Tokio".to_owned(), global, manager, &mut errors, &body);
+    }
+    #[ cfg(feature = "tokio_executor") ]
+    {
+        let manager = new_agnostic_executor().use_tokio_current_thread_executor();
+        TestHelper::test_wrapper_native("TokioCurrentThread".to_owned(), global, manager, &mut errors, &body);
+    }
+    #[ cfg(feature = "async_std_executor") ]
+    {
+        let manager = new_agnostic_executor().use_async_std_executor();
+        TestHelper::test_wrapper_native("AsyncStd".to_owned(), global, manager, &mut errors, &body);
+    }
+
+    #[ cfg(feature = "smol_executor") ]
+    {
+        let manager = new_agnostic_executor().use_smol_executor(None);
+        TestHelper::test_wrapper_native("Smol".to_owned(), global, manager, &mut errors, &body);
+    }
 
-    if global {
-        #[ cfg(feature = "tokio_executor") ]
-        {
-            let manager = new_agnostic_executor().use_tokio_executor();
-            if !check_global_executor() { manager.set_as_global() }
-            TestHelper::test_wrapper_native("Tokio".to_owned(), manager, &mut errors, &body);
-        }
-    } else {
-        #[ cfg(feature = "tokio_executor") ]
-        {
-            let manager = new_agnostic_executor().use_tokio_executor();
-            TestHelper::test_wrapper_native("Tokio".to_owned(), manager, &mut errors, &body);
-        }
-        #[ cfg(feature = "async_std_executor") ]
-        {
-            let manager = new_agnostic_executor().use_async_std_executor();
-            TestHelper::test_wrapper_native("AsyncStd".to_owned(), manager, &mut errors, &body);
-        }
+    #[ cfg(feature = "async_global_executor") ]
+    {
+        let manager = new_agnostic_executor().use_async_global_executor();
+        TestHelper::test_wrapper_native("AsyncGlobal".to_owned(), global, manager, &mut errors, &body);
+    }
 
-        #[ cfg(feature = "smol_executor") ]
-        {
-            let manager = new_agnostic_executor().use_smol_executor(None);
-            TestHelper::test_wrapper_native("Smol".to_owned(), manager, &mut errors, &body);
-        }
+    #[ cfg(feature = "futures_executor") ]
+    {
+        let manager = new_agnostic_executor().use_futures_executor();
+        TestHelper::test_wrapper_native("Futures".to_owned(), global, manager, &mut errors, &body);
+    }
 
-        #[ cfg(feature = "futures_executor") ]
-        {
-            let manager = new_agnostic_executor().use_futures_executor();
-            TestHelper::test_wrapper_native("Futures".to_owned(), manager, &mut errors, &body);
-        }
+    #[ cfg(feature = "simulation_executor") ]
+    for seed in seeds {
+        let manager = new_agnostic_executor().use_simulation_executor(seed);
+        TestHelper::test_wrapper_native(format!("Simulation(seed = {})", seed), global, manager, &mut errors, &body);
     }
 
     let without_errors = errors.is_empty();
@@ -20,6 +20,12 @@ mod wasm_time;
 #[cfg(feature = "wasm_bindgen_executor")]
 pub use wasm_time::*;
 
+mod virtual_time;
+
+pub use virtual_time::VirtualClock;
+pub(crate) use virtual_time::with_auto_advance;
+use virtual_time::*;
+
 
 fn to_millis(duration: Duration) -> u64 {
     (duration.as_secs_f64() * 1000.0) as u64
@@ -33,12 +39,13 @@ enum IntervalInner {
     Tokio(tokio::time::Interval),
     #[cfg(feature = "async_std_executor")]
     AsyncStd(async_std::stream::Interval),
-    #[cfg(feature = "smol_executor")]
+    #[cfg(any(feature = "smol_executor", feature = "async_global_executor", feature = "simulation_executor"))]
     Smol(std::cell::Cell<async_io::Timer>, Duration, std::time::Instant),
     #[cfg(feature = "futures_executor")]
     AsyncTimer(async_timer::Interval),
     #[cfg(feature = "wasm_bindgen_executor")]
-    WasmBindgen(WasmInterval)
+    WasmBindgen(WasmInterval),
+    Virtual(VirtualInterval)
 }
 
 /// An interval be used to retrieve a sequence of futures, each one expiring after a given interval from the previous one.
@@ -57,7 +64,7 @@ impl Interval {
                 use futures_lite::StreamExt;
                 interval.next().await;
             },
-            #[cfg(feature = "smol_executor")]
+            #[cfg(any(feature = "smol_executor", feature = "async_global_executor", feature = "simulation_executor"))]
             IntervalInner::Smol(timer, duration, at) => {
                 use std::ops::Add;
                 timer.get_mut().await;
@@ -71,6 +78,9 @@ impl Interval {
             #[cfg(feature = "wasm_bindgen_executor")]
             IntervalInner::WasmBindgen(wasm_interval) => {
                 wasm_interval.next().await;
+            },
+            IntervalInner::Virtual(virtual_interval) => {
+                virtual_interval.next().await;
             }
         }
     }
@@ -89,8 +99,17 @@ impl Interval {
 
 impl AgnosticExecutor {
 
+    /// Returns the virtual clock used by this executor, if any.
+    pub fn virtual_clock(&self) -> Option<&VirtualClock> {
+        self.clock.as_ref()
+    }
+
     /// Returns a future that sleeps for a duration.
     pub async fn sleep(&self, duration: Duration) {
+        if let Some(clock) = &self.clock {
+            return clock.sleep(duration).await;
+        }
+
         match &self.inner {
             #[cfg(feature = "tokio_executor")]
             TokioHandle(_) => {
@@ -104,8 +123,16 @@ impl AgnosticExecutor {
             SmolHandle(_) => {
                 async_io::Timer::after(duration).await;
             },
+            #[cfg(feature = "async_global_executor")]
+            AsyncGlobalHandle => {
+                async_io::Timer::after(duration).await;
+            },
+            #[cfg(feature = "simulation_executor")]
+            SimulationHandle(_) => {
+                async_io::Timer::after(duration).await;
+            },
             #[cfg(feature = "futures_executor")]
-            FuturesHandle(_) => {
+            FuturesHandle(..) => {
                 async_timer::new_timer(duration).await;
             },
             #[cfg(feature = "wasm_bindgen_executor")]
@@ -122,6 +149,11 @@ impl AgnosticExecutor {
 
     /// Wraps a future in a timeout that expires after a duration if the provided future didn't finish.
     pub async fn timeout<T: futures::Future>(&self, duration: Duration, future: T) -> Result<T::Output, TimedOut> {
+        if let Some(clock) = &self.clock {
+            futures::pin_mut!(future);
+            return VirtualTimeoutFuture::new(clock, future, duration).await;
+        }
+
         match &self.inner {
             #[cfg(feature = "tokio_executor")]
             TokioHandle(_) => {
@@ -139,8 +171,24 @@ impl AgnosticExecutor {
                     Ok(future.await)
                 }).await
             },
+            #[cfg(feature = "async_global_executor")]
+            AsyncGlobalHandle => {
+                futures_lite::future::or(async {
+                    async_io::Timer::after(duration).await; Err(TimedOut)
+                }, async {
+                    Ok(future.await)
+                }).await
+            },
+            #[cfg(feature = "simulation_executor")]
+            SimulationHandle(_) => {
+                futures_lite::future::or(async {
+                    async_io::Timer::after(duration).await; Err(TimedOut)
+                }, async {
+                    Ok(future.await)
+                }).await
+            },
             #[cfg(feature = "futures_executor")]
-            FuturesHandle(_) => {
+            FuturesHandle(..) => {
                 futures::pin_mut!(future);
                 async_timer::timed(future, duration).await.map_err(|_| TimedOut)
             },
@@ -159,6 +207,10 @@ impl AgnosticExecutor {
     
     /// Creates a new Interval for a given duration.
     pub fn interval(&self, duration: Duration) -> Interval {
+        if let Some(clock) = &self.clock {
+            return Interval(IntervalInner::Virtual(clock.interval(duration)));
+        }
+
         match &self.inner {
             #[cfg(feature = "tokio_executor")]
             TokioHandle(_) => {
@@ -178,8 +230,22 @@ impl AgnosticExecutor {
                 let timer = std::cell::Cell::new(async_io::Timer::at(at));
                 Interval(IntervalInner::Smol(timer, duration, at))
             },
+            #[cfg(feature = "async_global_executor")]
+            AsyncGlobalHandle => {
+                use std::ops::Add;
+                let at = std::time::Instant::now().add(duration);
+                let timer = std::cell::Cell::new(async_io::Timer::at(at));
+                Interval(IntervalInner::Smol(timer, duration, at))
+            },
+            #[cfg(feature = "simulation_executor")]
+            SimulationHandle(_) => {
+                use std::ops::Add;
+                let at = std::time::Instant::now().add(duration);
+                let timer = std::cell::Cell::new(async_io::Timer::at(at));
+                Interval(IntervalInner::Smol(timer, duration, at))
+            },
             #[cfg(feature = "futures_executor")]
-            FuturesHandle(_) => {
+            FuturesHandle(..) => {
                 Interval(IntervalInner::AsyncTimer(async_timer::interval(duration)))
             },
             #[cfg(feature = "wasm_bindgen_executor")]
@@ -194,14 +260,23 @@ impl AgnosticExecutor {
         self.interval(Duration::from_millis(duration))
     }
 
+    /// Creates a new Stopwatch that measures time using the clock of this executor, so that it follows the virtual clock if there is one.
+    pub fn stopwatch(&self) -> Stopwatch {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.virtual_start = self.clock.as_ref().map(|clock| (clock.clone(), clock.now()));
+        stopwatch
+    }
+
 }
 
 /// A Stopwatch can be used to measure time in a generic way that works even on wasm.
+/// Inside the tasks of an executor it follows its virtual clock, if there is one. Create it with AgnosticExecutor::stopwatch to follow the clock of an executor from anywhere else.
 pub struct Stopwatch {
     #[cfg(not(feature = "wasm_bindgen_executor"))]
     start: std::time::Instant,
     #[cfg(feature = "wasm_bindgen_executor")]
     start: u64,
+    virtual_start: Option<(VirtualClock, Duration)>,
     tolerance: u64
 }
 
@@ -223,6 +298,7 @@ impl Stopwatch {
             start: std::time::Instant::now(),
             #[cfg(feature = "wasm_bindgen_executor")]
             start: wasm_now() as u64,
+            virtual_start: crate::executors::current_clock().map(|clock| { let now = clock.now(); (clock, now) }),
             tolerance
         }  
     }
@@ -239,6 +315,9 @@ impl Stopwatch {
 
     /// Returns the duration since creation or last reset.
     pub fn elapsed(&self) -> Duration {
+        if let Some((clock, start)) = &self.virtual_start {
+            return clock.now() - *start;
+        }
         #[cfg(not(feature = "wasm_bindgen_executor"))]
         return self.start.elapsed();
         #[cfg(feature = "wasm_bindgen_executor")]
@@ -247,6 +326,9 @@ impl Stopwatch {
 
     /// Returns the duration in milliseconds since creation or last reset.
     pub fn elapsed_millis(&self) -> u64 {
+        if self.virtual_start.is_some() {
+            return to_millis(self.elapsed());
+        }
         #[cfg(not(feature = "wasm_bindgen_executor"))]
         return to_millis(self.start.elapsed());
         #[cfg(feature = "wasm_bindgen_executor")]
@@ -265,6 +347,9 @@ impl Stopwatch {
 
     /// Resets this instance so that the elapsed time is measured from this instant.
     pub fn reset(&mut self) {
+        if let Some((clock, start)) = &mut self.virtual_start {
+            *start = clock.now();
+        }
         #[cfg(not(feature = "wasm_bindgen_executor"))]
         { self.start = std::time::Instant::now(); }
         #[cfg(feature = "wasm_bindgen_executor")]
@@ -0,0 +1,276 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    time::Duration
+};
+
+use crate::time::TimedOut;
+use crate::executors::{RunnableWaker, TaskTracker};
+
+struct ClockState {
+    now: Duration,
+    sleepers: BTreeMap<(Duration, u64), Waker>,
+    next_id: u64,
+    idle_waker: Option<Waker>
+}
+
+/// A controllable clock that replaces the real one for the time utilities of the executors it's set on.
+///
+/// Time only moves forward when advance is called or, if the clock was created with auto advance, when every task of the executor is waiting for the clock.
+/// In that case it jumps directly to the next deadline, so tests that sleep for minutes finish immediately.
+/// The executor is idle when none of its tasks is woken or being polled, which is tracked through their wakers, so it works on any number of threads.
+/// Tasks woken by something that isn't a task of the executor, like a thread spawned with std or an io driver, can't be seen, so they shouldn't be mixed with an auto advancing clock.
+/// It never advances while a blocking task of the executor is queued or running, so blocking tasks must not wait for the clock.
+#[derive(Clone)]
+pub struct VirtualClock {
+    state: Arc<Mutex<ClockState>>,
+    auto_advance: bool
+}
+
+impl fmt::Debug for VirtualClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VirtualClock").field("now", &self.now()).field("auto_advance", &self.auto_advance).finish()
+    }
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        VirtualClock::new()
+    }
+}
+
+impl VirtualClock {
+    /// Creates a new paused clock that only moves forward when advance is called.
+    pub fn new() -> Self {
+        VirtualClock::with_auto_advance(false)
+    }
+
+    /// Creates a new clock that automatically advances to the next deadline when the executor is idle.
+    pub fn new_auto_advance() -> Self {
+        VirtualClock::with_auto_advance(true)
+    }
+
+    fn with_auto_advance(auto_advance: bool) -> Self {
+        let state = ClockState { now: Duration::ZERO, sleepers: BTreeMap::new(), next_id: 0, idle_waker: None };
+        VirtualClock { state: Arc::new(Mutex::new(state)), auto_advance }
+    }
+
+    /// Returns true if this clock advances automatically when the executor is idle.
+    pub fn is_auto_advance(&self) -> bool {
+        self.auto_advance
+    }
+
+    /// Returns the virtual time elapsed since the clock was created.
+    pub fn now(&self) -> Duration {
+        self.state.lock().unwrap().now
+    }
+
+    /// Moves the clock forward, waking every sleep, timeout and interval whose deadline has been reached.
+    pub fn advance(&self, duration: Duration) {
+        let wakers = {
+            let mut state = self.state.lock().unwrap();
+            state.now += duration;
+            Self::take_expired(&mut state)
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Moves the clock forward a duration in milliseconds.
+    pub fn advance_millis(&self, duration: u64) {
+        self.advance(Duration::from_millis(duration));
+    }
+
+    /// Moves the clock forward to the next pending deadline, if any. Returns false if nothing was waiting for the clock.
+    pub fn advance_to_next(&self) -> bool {
+        let next = {
+            let state = self.state.lock().unwrap();
+            state.sleepers.keys().next().map(|(deadline, _)| deadline.saturating_sub(state.now))
+        };
+        match next {
+            Some(duration) => { self.advance(duration); true },
+            None => false
+        }
+    }
+
+    fn take_expired(state: &mut ClockState) -> Vec<Waker> {
+        let pending = state.sleepers.split_off(&(state.now, u64::MAX));
+        std::mem::replace(&mut state.sleepers, pending).into_values().collect()
+    }
+
+    pub(crate) fn sleep(&self, duration: Duration) -> VirtualSleep {
+        let deadline = self.now() + duration;
+        VirtualSleep { clock: self.clone(), deadline, id: None }
+    }
+
+    pub(crate) fn interval(&self, duration: Duration) -> VirtualInterval {
+        VirtualInterval { clock: self.clone(), period: duration, next: self.now() + duration }
+    }
+
+    fn poll_sleepers(&self, cx: &mut Context) -> Poll<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.sleepers.is_empty() {
+            state.idle_waker = Some(cx.waker().clone());
+            Poll::Pending
+        } else {
+            Poll::Ready(())
+        }
+    }
+}
+
+pub(crate) struct VirtualSleep {
+    clock: VirtualClock,
+    deadline: Duration,
+    id: Option<u64>
+}
+
+impl Future for VirtualSleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let deadline = self.deadline;
+        let mut state = self.clock.state.lock().unwrap();
+        if state.now >= deadline {
+            if let Some(id) = self.id {
+                state.sleepers.remove(&(deadline, id));
+            }
+            drop(state);
+            self.id = None;
+            return Poll::Ready(());
+        }
+
+        let id = match self.id {
+            Some(id) => id,
+            None => {
+                let id = state.next_id;
+                state.next_id += 1;
+                id
+            }
+        };
+        state.sleepers.insert((deadline, id), cx.waker().clone());
+        let idle_waker = state.idle_waker.take();
+        drop(state);
+        self.id = Some(id);
+
+        if let Some(waker) = idle_waker {
+            waker.wake();
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for VirtualSleep {
+    fn drop(&mut self) {
+        if let Some(id) = self.id.take() {
+            let mut state = self.clock.state.lock().unwrap();
+            state.sleepers.remove(&(self.deadline, id));
+        }
+    }
+}
+
+pub(crate) struct VirtualTimeoutFuture<F> where F: Future + Unpin {
+    future: F,
+    timeout: VirtualSleep
+}
+
+impl<F> VirtualTimeoutFuture<F> where F: Future + Unpin {
+    pub fn new(clock: &VirtualClock, future: F, duration: Duration) -> Self {
+        VirtualTimeoutFuture {
+            future,
+            timeout: clock.sleep(duration)
+        }
+    }
+}
+
+impl<F> Future for VirtualTimeoutFuture<F> where F: Future + Unpin {
+    type Output = Result<F::Output, TimedOut>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if let Poll::Ready(t) = Pin::new(&mut self.future).poll(cx) {
+            return Poll::Ready(Ok(t));
+        }
+
+        if Pin::new(&mut self.timeout).poll(cx).is_ready() {
+            return Poll::Ready(Err(TimedOut));
+        }
+
+        Poll::Pending
+    }
+}
+
+pub(crate) struct VirtualInterval {
+    clock: VirtualClock,
+    period: Duration,
+    next: Duration
+}
+
+impl VirtualInterval {
+    pub(crate) async fn next(&mut self) {
+        let remaining = self.next.saturating_sub(self.clock.now());
+        self.clock.sleep(remaining).await;
+        self.next += self.period;
+    }
+}
+
+async fn auto_advance(clock: VirtualClock, tracker: TaskTracker) {
+    loop {
+        std::future::poll_fn(|cx| clock.poll_sleepers(cx)).await;
+        // Blocking tasks make progress without being polled, so the executor isn't idle until they finish
+        tracker.blocking_idle().await;
+        tracker.runnable_idle().await;
+
+        if tracker.blocking() == 0 && tracker.runnable() == 0 {
+            clock.advance_to_next();
+        }
+    }
+}
+
+/// A future that drives the root future of an executor, advancing the virtual clock when every other task is waiting for it.
+/// The root is counted as a runnable task too, and it's only polled when it has been woken, so the driver doesn't keep it busy.
+pub(crate) struct AutoAdvance<F> {
+    future: Pin<Box<F>>,
+    driver: Option<Pin<Box<dyn Future<Output = ()>>>>,
+    runnable: Option<(Arc<RunnableWaker>, Waker)>,
+    waker: Option<Waker>
+}
+
+impl<F: Future> Future for AutoAdvance<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let res = match &this.runnable {
+            Some((runnable, waker)) => {
+                if !this.waker.as_ref().is_some_and(|current| current.will_wake(cx.waker())) {
+                    this.waker = Some(cx.waker().clone());
+                    runnable.set_waker(cx.waker());
+                }
+                match runnable.try_enter() {
+                    Some(_running) => this.future.as_mut().poll(&mut Context::from_waker(waker)),
+                    None => Poll::Pending // Only the driver was woken
+                }
+            },
+            None => this.future.as_mut().poll(cx)
+        };
+        if res.is_ready() {
+            return res;
+        }
+
+        if let Some(driver) = &mut this.driver {
+            let _ = driver.as_mut().poll(cx); // It never completes
+        }
+
+        Poll::Pending
+    }
+}
+
+pub(crate) fn with_auto_advance<F: Future>(clock: Option<&VirtualClock>, tracker: &TaskTracker, future: F) -> AutoAdvance<F> {
+    let driver = clock
+        .filter(|clock| clock.is_auto_advance())
+        .map(|clock| Box::pin(auto_advance(clock.clone(), tracker.clone())) as Pin<Box<dyn Future<Output = ()>>>);
+    let runnable = driver.as_ref().and_then(|_| tracker.runnable_waker());
+    AutoAdvance { future: Box::pin(future), driver, runnable, waker: None }
+}
@@ -1,7 +1,12 @@
 pub(crate) mod common_tests {
-    use agnostic_async_executor::{AgnosticExecutorManager, test::*, time::Stopwatch};
+    use agnostic_async_executor::{task_local, AgnosticExecutor, AgnosticExecutorManager, ExecutorKind, ShutdownReport, TaskSet, current_task_id, current_task_name, test::*, time::{Stopwatch, VirtualClock}};
     use futures::channel::oneshot;
 
+    // Wasm has no threads for blocking and compute tasks, so they are rejected
+    fn supports_threads(helper: &TestHelper) -> bool {
+        helper.get_runtime_name() != "WasmBindgen"
+    }
+
     pub fn common_test_spawn(manager: AgnosticExecutorManager, mut helper: TestHelper) {
         let exec = manager.get_executor();
         manager.start(async move{
@@ -15,6 +20,12 @@ pub(crate) mod common_tests {
     pub fn common_test_spawn_blocking(manager: AgnosticExecutorManager, mut helper: TestHelper) {
         let exec = manager.get_executor();
         manager.start(async move{
+            if !supports_threads(&helper) {
+                let res = exec.spawn_blocking(|| 1i32).try_join().await;
+                check!(helper, matches!(&res, Err(e) if e.is_rejected()));
+                return;
+            }
+
             let res = exec.spawn_blocking(|| {
                 1i32
             }).await;
@@ -22,6 +33,16 @@ pub(crate) mod common_tests {
         });
     }
 
+    pub fn common_test_run(manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        let exec = manager.get_executor();
+        let res = manager.run(async move{
+            exec.spawn(async {
+                1i32
+            }).await + 1
+        });
+        check!(helper, res == 2);
+    }
+
     #[cfg(feature = "block_on")]
     pub fn common_test_block_on(manager: AgnosticExecutorManager, mut helper: TestHelper) {
         let exec = manager.get_executor();        
@@ -64,6 +85,112 @@ pub(crate) mod common_tests {
         });
     }
 
+    pub fn common_test_virtual_time(mut manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        manager.set_virtual_clock(VirtualClock::new_auto_advance());
+        let exec = manager.get_executor();
+        let real_sw = Stopwatch::new();
+        manager.start(async move{
+            let sw = exec.stopwatch();
+            let task_sw = Stopwatch::new();
+
+            exec.sleep(std::time::Duration::from_secs(60)).await;
+            check!(helper, sw.elapsed() == std::time::Duration::from_secs(60));
+            check!(helper, task_sw.elapsed() == std::time::Duration::from_secs(60));
+
+            let res = exec.timeout(std::time::Duration::from_secs(10), async {
+                exec.sleep(std::time::Duration::from_secs(20)).await
+            }).await;
+            check!(helper, res.is_err());
+            check!(helper, sw.elapsed() == std::time::Duration::from_secs(70));
+
+            let exec2 = exec.clone();
+            let res = exec.spawn(async move {
+                exec2.sleep(std::time::Duration::from_secs(30)).await;
+                1i32
+            }).await;
+            check!(helper, res == 1);
+            check!(helper, sw.elapsed() == std::time::Duration::from_secs(100));
+
+            let mut interval = exec.interval(std::time::Duration::from_secs(1));
+            for i in 1..10u64 {
+                interval.next().await;
+                check!(helper, sw.elapsed() == std::time::Duration::from_secs(100 + i));
+            }
+
+            check!(helper, real_sw.elapsed() < std::time::Duration::from_secs(10));
+        });
+    }
+
+    // The clock doesn't advance while a task is busy, even if it never touches the clock
+    pub fn common_test_virtual_time_busy(mut manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        manager.set_virtual_clock(VirtualClock::new_auto_advance());
+        let exec = manager.get_executor();
+        manager.start(async move{
+            let sw = exec.stopwatch();
+            let busy = exec.spawn(async {
+                for _ in 0..1000 {
+                    let mut yielded = false;
+                    std::future::poll_fn(|cx| {
+                        if yielded {
+                            return std::task::Poll::Ready(());
+                        }
+                        yielded = true;
+                        cx.waker().wake_by_ref();
+                        std::task::Poll::Pending
+                    }).await;
+                }
+            });
+
+            let res = exec.timeout(std::time::Duration::from_secs(1), busy).await;
+            check!(helper, res.is_ok());
+            check!(helper, sw.elapsed() == std::time::Duration::ZERO);
+        });
+    }
+
+    // The clock doesn't advance while a blocking task runs, even if every async task is waiting for it
+    pub fn common_test_virtual_time_blocking(mut manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        if !supports_threads(&helper) {
+            return;
+        }
+        let clock = VirtualClock::new_auto_advance();
+        manager.set_virtual_clock(clock.clone());
+        let exec = manager.get_executor();
+        manager.start(async move{
+            let exec2 = exec.clone();
+            let sleeper = exec.spawn(async move {
+                exec2.sleep(std::time::Duration::from_secs(3600)).await;
+            });
+
+            let now = exec.spawn_blocking(move || {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                clock.now()
+            }).await;
+            check!(helper, now == std::time::Duration::ZERO);
+
+            sleeper.await;
+        });
+    }
+
+    pub fn common_test_virtual_advance(mut manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        let clock = VirtualClock::new();
+        manager.set_virtual_clock(clock.clone());
+        let exec = manager.get_executor();
+        manager.start(async move{
+            let sw = exec.stopwatch();
+            let mut interval = exec.interval_millis(100);
+
+            clock.advance_millis(100);
+            interval.next().await;
+            check!(helper, sw.elapsed_millis() == 100);
+
+            clock.advance_millis(250);
+            interval.next().await;
+            interval.next().await;
+            check!(helper, sw.elapsed_millis() == 350);
+            check!(helper, clock.now() == std::time::Duration::from_millis(350));
+        });
+    }
+
     pub fn common_test_local(mut manager: AgnosticExecutorManager, mut helper: TestHelper) {
         let not_send_number = std::rc::Rc::new(1i32);
         let send_number = std::sync::Arc::new(2i32);
@@ -85,6 +212,23 @@ pub(crate) mod common_tests {
         });
     }
 
+    // A local task still pending when the executor stops is dropped with it, and its handle reports it as cancelled
+    pub fn common_test_local_dropped(mut manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        if !matches!(manager.get_executor().kind(), ExecutorKind::Tokio | ExecutorKind::Futures) {
+            return; // The other executors keep their local tasks or can't poll the handle afterwards
+        }
+        let local = manager.get_local_executor();
+        let (handle_tx, handle_rx) = std::sync::mpsc::channel();
+        manager.start(async move {
+            let handle = local.spawn_local(futures::future::pending::<i32>());
+            handle_tx.send(handle).unwrap();
+        });
+
+        let handle = handle_rx.recv().unwrap();
+        let res = futures::executor::block_on(handle.try_join());
+        check!(helper, matches!(res, Err(e) if e.is_cancelled()));
+    }
+
     pub fn common_test_cancel_handle(manager: AgnosticExecutorManager, mut helper: TestHelper) {
         let exec = manager.get_executor();
         manager.start(async move{
@@ -131,8 +275,622 @@ pub(crate) mod common_tests {
         });
     }
 
+    #[cfg(feature = "compute")]
+    pub fn common_test_spawn_compute(manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        let exec = manager.get_executor();
+        manager.start(async move{
+            if !supports_threads(&helper) {
+                let res = exec.spawn_compute(|| 1i32).try_join().await;
+                check!(helper, matches!(&res, Err(e) if e.is_rejected()));
+                return;
+            }
+
+            let handles: Vec<_> = (0..16u64).map(|n| exec.spawn_compute(move || (0..=n * 1000).sum::<u64>())).collect();
+            let mut res = Vec::new();
+            for handle in handles {
+                res.push(handle.await);
+            }
+            check!(helper, res == (0..16u64).map(|n| n * 1000 * (n * 1000 + 1) / 2).collect::<Vec<_>>());
+
+            // Nested compute tasks are queued on the thread of their parent
+            let res = exec.spawn_compute(|| {
+                let exec = AgnosticExecutor::current().expect("No current executor on a compute task");
+                exec.spawn_compute(|| 2i32)
+            }).await.await;
+            check!(helper, res == 2);
+
+            let handle = exec.task().name("compute worker").spawn_compute(current_task_name);
+            check!(helper, handle.await == Some("compute worker".to_owned()));
+
+            let res = exec.spawn_compute(|| {
+                if true { panic!("Expected panic"); }
+                1i32
+            }).try_join().await;
+            check!(helper, matches!(&res, Err(e) if e.is_panic()));
+        });
+    }
+
+    #[cfg(feature = "rayon")]
+    pub fn common_test_spawn_rayon(manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        let exec = manager.get_executor();
+        manager.start(async move{
+            if !supports_threads(&helper) {
+                let res = exec.par_map_async(vec![1i32, 2], |n| n * 2).try_join().await;
+                check!(helper, matches!(&res, Err(e) if e.is_rejected()));
+                return;
+            }
+
+            let res = exec.spawn_rayon(|| {
+                use rayon::prelude::*;
+                (0..1000u64).into_par_iter().sum::<u64>()
+            }).await;
+            check!(helper, res == 499500);
+
+            let res = exec.par_map_async(0..100u64, |n| n * n).await;
+            check!(helper, res == (0..100u64).map(|n| n * n).collect::<Vec<_>>());
+
+            let handle = exec.task().name("rayon worker").spawn_rayon(current_task_name);
+            check!(helper, handle.await == Some("rayon worker".to_owned()));
+
+            let res = exec.spawn_rayon(|| {
+                if true { panic!("Expected panic"); }
+                1i32
+            }).try_join().await;
+            check!(helper, matches!(&res, Err(e) if e.is_panic()));
+        });
+    }
+
+    pub fn common_test_try_join(manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        let exec = manager.get_executor();
+        manager.start(async move{
+            let res = exec.spawn(async {
+                1i32
+            }).try_join().await;
+            check!(helper, matches!(res, Ok(1)));
+
+            let res = exec.spawn(async {
+                if true { panic!("Expected panic"); }
+                1i32
+            }).try_join().await;
+            check!(helper, matches!(&res, Err(e) if e.is_panic()));
+
+            if let Err(e) = res {
+                check!(helper, e.into_panic().downcast_ref::<&str>() == Some(&"Expected panic"));
+            }
+
+            if supports_threads(&helper) {
+                let res = exec.spawn_blocking(|| {
+                    if true { panic!("Expected panic"); }
+                    1i32
+                }).try_join().await;
+                check!(helper, matches!(&res, Err(e) if e.is_panic()));
+            }
+        });
+    }
+
+    pub fn common_test_interleaving(manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        let exec = manager.get_executor();
+        manager.start(async move{
+            let counter = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+            let handles: Vec<_> = (0..10).map(|_| {
+                let counter = counter.clone();
+                exec.spawn(async move {
+                    counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst)
+                })
+            }).collect();
+
+            let mut res = Vec::new();
+            for handle in handles {
+                res.push(handle.await);
+            }
+            res.sort();
+
+            check!(helper, res == (0..10).collect::<Vec<_>>());
+        });
+    }
+
+    pub fn common_test_shutdown(mut manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        manager.set_shutdown_grace_period(std::time::Duration::from_secs(10));
+        let exec = manager.get_executor();
+        let shutdown = manager.shutdown_handle();
+        let shutdown2 = shutdown.clone();
+
+        let (c_tx, mut c_rx) = oneshot::channel();
+
+        let mut inner_helper = helper.clone();
+        manager.start(async move{
+            let exec2 = exec.clone();
+            exec.spawn(async move {
+                exec2.shutdown_signal().await;
+                exec2.sleep(std::time::Duration::from_millis(10)).await; // Some cleanup work
+                c_tx.send(1i32).unwrap();
+            });
+
+            std::thread::spawn(move || shutdown2.shutdown()).join().unwrap();
+            exec.shutdown_signal().await;
+            check!(inner_helper, exec.is_shutting_down());
+        });
+
+        check!(helper, shutdown.report() == Some(ShutdownReport { still_alive: 0 }));
+        check!(helper, c_rx.try_recv() == Ok(Some(1)));
+    }
+
+    pub fn common_test_shutdown_grace_period(mut manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        manager.set_virtual_clock(VirtualClock::new_auto_advance());
+        manager.set_shutdown_grace_period(std::time::Duration::from_secs(10));
+        let exec = manager.get_executor();
+        let shutdown = manager.shutdown_handle();
+        let shutdown2 = shutdown.clone();
+
+        let (c_tx, mut c_rx) = oneshot::channel();
+
+        let mut inner_helper = helper.clone();
+        manager.start(async move{
+            let exec2 = exec.clone();
+            let handle = exec.spawn(async move {
+                exec2.sleep(std::time::Duration::from_secs(3600)).await; // Ignores the shutdown signal
+                c_tx.send(1i32).unwrap();
+            });
+
+            let exec3 = exec.clone();
+            exec.spawn(async move {
+                let res = handle.try_join().await;
+                check!(inner_helper, matches!(res, Err(e) if e.is_cancelled()));
+                exec3.sleep(std::time::Duration::from_secs(3600)).await;
+            });
+
+            shutdown2.shutdown();
+        });
+
+        check!(helper, shutdown.report() == Some(ShutdownReport { still_alive: 2 }));
+        check!(helper, c_rx.try_recv().is_err()); // The cancelled task was dropped without sending
+    }
+
+    // The root future never completes, so it's cancelled with the rest of the tasks when the grace period expires
+    pub fn common_test_shutdown_root_cancelled(mut manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        manager.set_virtual_clock(VirtualClock::new_auto_advance());
+        manager.set_shutdown_grace_period(std::time::Duration::from_millis(100));
+        let shutdown = manager.shutdown_handle();
+        let shutdown2 = shutdown.clone();
+
+        let res = manager.try_run(async move{
+            std::thread::spawn(move || shutdown2.shutdown()).join().unwrap();
+            futures::future::pending::<i32>().await
+        });
+
+        check!(helper, matches!(res, Err(e) if e.is_cancelled()));
+        check!(helper, shutdown.report() == Some(ShutdownReport { still_alive: 0 }));
+    }
+
+    // A blocking task that ignores the shutdown is left running when the grace period expires, instead of blocking the start call
+    pub fn common_test_shutdown_stuck_blocking(mut manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        let exec = manager.get_executor();
+        if !supports_threads(&helper) || exec.kind() == ExecutorKind::Simulation {
+            return; // The simulation runs blocking tasks inline
+        }
+        manager.set_shutdown_grace_period(std::time::Duration::from_millis(100));
+        let shutdown = manager.shutdown_handle();
+        let shutdown2 = shutdown.clone();
+
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+        let started = std::time::Instant::now();
+        manager.start(async move{
+            exec.spawn_blocking(move || { let _ = release_rx.recv_timeout(std::time::Duration::from_secs(10)); });
+            shutdown2.shutdown();
+        });
+
+        check!(helper, started.elapsed() < std::time::Duration::from_secs(5));
+        check!(helper, shutdown.report() == Some(ShutdownReport { still_alive: 1 }));
+        drop(release_tx);
+    }
+
+    pub fn common_test_current_executor(mut manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        let local = manager.get_local_executor();
+        check!(helper, AgnosticExecutor::current().is_none());
+        manager.start(async move{
+            let exec = AgnosticExecutor::current().expect("No current executor on the root future");
+            let res = exec.spawn(async {
+                let exec = AgnosticExecutor::current().expect("No current executor on a spawned task");
+                exec.spawn(async { 1i32 }).await + 1
+            }).await;
+            check!(helper, res == 2);
+
+            if supports_threads(&helper) {
+                let res = exec.spawn_blocking(|| {
+                    AgnosticExecutor::current().is_some()
+                }).await;
+                check!(helper, res);
+            }
+
+            let res = local.spawn_local(async {
+                let exec = AgnosticExecutor::current().expect("No current executor on a local task");
+                exec.spawn(async { 3i32 }).await
+            }).await;
+            check!(helper, res == 3);
+        });
+    }
+
+    pub fn common_test_from_ambient(manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        let exec = manager.get_executor();
+        let detectable = ["Tokio", "TokioCurrentThread", "AsyncStd"].contains(&helper.get_runtime_name());
+        check!(helper, AgnosticExecutor::from_ambient().is_none());
+        manager.start(async move{
+            let res = exec.spawn(async {
+                match AgnosticExecutor::from_ambient() {
+                    Some(ambient) => Some(ambient.spawn(async { 1i32 }).await),
+                    None => None
+                }
+            }).await;
+            check!(helper, res == if detectable { Some(1) } else { None });
+        });
+    }
+
+    pub fn common_test_kind(manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        let exec = manager.get_executor();
+        let kind = exec.kind();
+        let expected = match helper.get_runtime_name() {
+            "Tokio" | "TokioCurrentThread" => ExecutorKind::Tokio,
+            "AsyncStd" => ExecutorKind::AsyncStd,
+            "Smol" => ExecutorKind::Smol,
+            "AsyncGlobal" => ExecutorKind::AsyncGlobal,
+            "Futures" => ExecutorKind::Futures,
+            "WasmBindgen" => ExecutorKind::WasmBindgen,
+            _ => ExecutorKind::Simulation
+        };
+        check!(helper, kind == expected);
+
+        #[cfg(feature = "tokio_executor")]
+        check!(helper, exec.as_tokio_handle().is_some() == (kind == ExecutorKind::Tokio));
+        #[cfg(feature = "smol_executor")]
+        check!(helper, exec.as_smol_executor().is_some() == (kind == ExecutorKind::Smol));
+        #[cfg(feature = "futures_executor")]
+        check!(helper, exec.as_futures_thread_pool().is_some() == (kind == ExecutorKind::Futures));
+
+        manager.start(async move{
+            #[cfg(feature = "tokio_executor")]
+            if let Some(handle) = exec.as_tokio_handle() {
+                check!(helper, handle.spawn(async { 1i32 }).await.unwrap() == 1);
+            }
+        });
+    }
+
+    pub fn common_test_task_builder(mut manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        let exec = manager.get_executor();
+        let local = manager.get_local_executor();
+        manager.start(async move{
+            let handle = exec.task().name("worker").spawn(async {
+                (current_task_id(), current_task_name())
+            });
+            let id = handle.id();
+            check!(helper, handle.name() == Some("worker"));
+            check!(helper, handle.await == (Some(id), Some("worker".to_owned())));
+
+            if supports_threads(&helper) {
+                let handle = exec.task().name("blocking worker").spawn_blocking(|| {
+                    (current_task_id(), current_task_name())
+                });
+                let id = handle.id();
+                check!(helper, handle.await == (Some(id), Some("blocking worker".to_owned())));
+            }
+
+            let handle = local.task().name("local worker").spawn_local(async {
+                current_task_name()
+            });
+            check!(helper, handle.await == Some("local worker".to_owned()));
+
+            let handle = exec.spawn(async { current_task_id() });
+            let id = handle.id();
+            check!(helper, handle.name().is_none());
+            check!(helper, handle.await == Some(id));
+            check!(helper, exec.spawn(async { 1i32 }).id() != id);
+
+            let handle = exec.task().name("failing worker").spawn(async {
+                if true { panic!("Expected panic"); }
+                1i32
+            });
+            let id = handle.id();
+            let res = handle.try_join().await;
+            if let Err(e) = res {
+                check!(helper, e.id() == Some(id));
+                check!(helper, e.task_name() == Some("failing worker"));
+                check!(helper, e.to_string() == format!("task {} 'failing worker' panicked with message \"Expected panic\"", id));
+            } else {
+                check!(helper, false);
+            }
+        });
+    }
+
+    task_local! {
+        static REQUEST_ID: u32;
+    }
+
+    pub fn common_test_task_local(manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        let exec = manager.get_executor();
+        manager.start(async move{
+            check!(helper, REQUEST_ID.try_with(|id| *id).is_err());
+
+            let handles: Vec<_> = (0..3u32).map(|id| {
+                let exec2 = exec.clone();
+                exec.spawn(REQUEST_ID.scope(id, async move {
+                    exec2.sleep_millis(10 * (3 - id as u64)).await;
+                    let outer = REQUEST_ID.get();
+                    let inner = REQUEST_ID.scope(id + 10, async { REQUEST_ID.get() }).await;
+                    (outer, inner, REQUEST_ID.get())
+                }))
+            }).collect();
+            for (id, handle) in handles.into_iter().enumerate() {
+                let id = id as u32;
+                check!(helper, handle.await == (id, id + 10, id));
+            }
+
+            let res = REQUEST_ID.scope(1, async {
+                let id = REQUEST_ID.get();
+                exec.spawn(REQUEST_ID.scope(id, async { REQUEST_ID.get() })).await // Propagated explicitly
+            }).await;
+            check!(helper, res == 1);
+            check!(helper, REQUEST_ID.sync_scope(2, || REQUEST_ID.get()) == 2);
+            check!(helper, REQUEST_ID.try_with(|id| *id).is_err());
+        });
+    }
+
+    pub fn common_test_try_take(manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        let exec = manager.get_executor();
+        manager.start(async move{
+            let exec2 = exec.clone();
+            let mut handle = exec.spawn(async move {
+                exec2.sleep_millis(50).await;
+                1i32
+            });
+            check!(helper, !handle.is_finished());
+            check!(helper, handle.try_take().is_none());
+
+            exec.sleep_millis(100).await;
+            check!(helper, handle.is_finished());
+            let mut res = None;
+            for _ in 0..10 {
+                res = handle.try_take();
+                if res.is_some() {
+                    break;
+                }
+                exec.sleep_millis(10).await; // The result might not be available immediately on every executor
+            }
+            check!(helper, matches!(res, Some(Ok(1))));
+            check!(helper, handle.try_take().is_none());
+            check!(helper, handle.is_finished());
+
+            let res = handle.try_join().await;
+            check!(helper, matches!(&res, Err(e) if e.is_taken()));
+        });
+    }
+
+    pub fn common_test_abort_handle(manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        let exec = manager.get_executor();
+        manager.start(async move{
+            let (c_tx, mut c_rx) = oneshot::channel::<i32>();
+            let exec2 = exec.clone();
+            let handle = exec.spawn(async move {
+                exec2.sleep_millis(50).await;
+                c_tx.send(1).unwrap();
+                1i32
+            });
+            let abort = handle.abort_handle();
+            check!(helper, !abort.is_finished());
+
+            let abort2 = abort.clone();
+            exec.spawn(async move { abort2.abort() }).await;
+            let res = handle.try_join().await;
+            check!(helper, matches!(res, Err(e) if e.is_cancelled()));
+            check!(helper, abort.is_finished());
+
+            let handle = exec.spawn(async { 1i32 });
+            let abort = handle.abort_handle();
+            check!(helper, handle.await == 1);
+            check!(helper, abort.is_finished());
+            abort.abort(); // Does nothing once finished
+
+            exec.sleep_millis(100).await;
+            check!(helper, c_rx.try_recv().is_err());
+        });
+    }
+
+    pub fn common_test_abort_on_drop(manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        let exec = manager.get_executor();
+        manager.start(async move{
+            let handle = exec.spawn(async { 1i32 }).abort_on_drop();
+            check!(helper, handle.await == 1);
+
+            let (c_tx, mut c_rx) = oneshot::channel::<i32>();
+            let exec2 = exec.clone();
+            let handle = exec.spawn(async move {
+                exec2.sleep_millis(50).await;
+                c_tx.send(1).unwrap();
+            }).abort_on_drop();
+            let abort = handle.abort_handle();
+            drop(handle);
+
+            let (d_tx, mut d_rx) = oneshot::channel::<i32>();
+            if supports_threads(&helper) {
+                let handle = exec.spawn_blocking(move || {
+                    d_tx.send(1).unwrap();
+                    1i32
+                }).abort_on_drop();
+                check!(helper, handle.try_join().await.unwrap() == 1);
+            } else {
+                d_tx.send(1).unwrap();
+            }
+
+            exec.sleep_millis(100).await;
+            check!(helper, abort.is_finished());
+            check!(helper, c_rx.try_recv().is_err());
+            check!(helper, d_rx.try_recv() == Ok(Some(1)));
+        });
+    }
+
+    pub fn common_test_shared_join_handle(manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        let exec = manager.get_executor();
+        manager.start(async move{
+            let exec2 = exec.clone();
+            let handle = exec.spawn(async move {
+                exec2.sleep_millis(50).await;
+                "result".to_owned()
+            }).shared();
+
+            let waiters: Vec<_> = (0..3).map(|_| exec.spawn(handle.clone())).collect();
+            for waiter in waiters {
+                check!(helper, waiter.await == "result");
+            }
+            check!(helper, handle.clone().try_join().await.unwrap() == "result");
+
+            // Only cancelled once every clone alive requests it
+            let (c_tx, mut c_rx) = oneshot::channel::<i32>();
+            let exec2 = exec.clone();
+            let handle = exec.spawn(async move {
+                exec2.sleep_millis(50).await;
+                c_tx.send(1).unwrap();
+                1i32
+            }).shared();
+            let handle2 = handle.clone();
+            handle.cancel().await;
+            check!(helper, handle2.clone().await == 1);
+            check!(helper, c_rx.try_recv() == Ok(Some(1)));
+
+            let (c_tx, mut c_rx) = oneshot::channel::<i32>();
+            let exec2 = exec.clone();
+            let handle = exec.spawn(async move {
+                exec2.sleep_millis(50).await;
+                c_tx.send(1).unwrap();
+                1i32
+            }).shared();
+            let handle2 = handle.clone();
+            handle.cancel().await;
+            handle2.cancel().await;
+
+            let (d_tx, mut d_rx) = oneshot::channel::<i32>();
+            let exec2 = exec.clone();
+            let handle = exec.spawn(async move {
+                exec2.sleep_millis(50).await;
+                d_tx.send(1).unwrap();
+                1i32
+            }).shared();
+            handle.set_cancel_on_drop(true);
+            drop(handle.clone());
+            drop(handle);
+
+            exec.sleep_millis(100).await;
+            check!(helper, c_rx.try_recv().is_err());
+            check!(helper, d_rx.try_recv().is_err());
+        });
+    }
+
+    pub fn common_test_task_set(manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        let exec = manager.get_executor();
+        manager.start(async move{
+            let mut set = TaskSet::new(&exec);
+            for value in [3u64, 1, 2] {
+                let exec = exec.clone();
+                set.spawn(async move {
+                    exec.sleep_millis(50 * value).await;
+                    value
+                });
+            }
+            if supports_threads(&helper) {
+                set.spawn_blocking(|| 0);
+            } else {
+                set.spawn(async { 0 });
+            }
+            check!(helper, set.len() == 4);
+
+            let mut res = Vec::new();
+            while let Some(value) = set.join_next().await {
+                res.push(value.unwrap());
+            }
+            check!(helper, res == vec![0, 1, 2, 3]);
+            check!(helper, set.is_empty());
+
+            let (c_tx, mut c_rx) = oneshot::channel::<i32>();
+            let exec2 = exec.clone();
+            set.spawn(async move {
+                exec2.sleep_millis(50).await;
+                c_tx.send(1).unwrap();
+                1
+            });
+            set.abort_all();
+            check!(helper, set.join_next().await.is_none());
+
+            // A join_next that is dropped before completing doesn't lose the tasks woken in the meantime
+            let exec2 = exec.clone();
+            set.spawn(async move {
+                exec2.sleep_millis(100).await;
+                7
+            });
+            let res = exec.timeout(std::time::Duration::from_millis(10), set.join_next()).await;
+            check!(helper, res.is_err());
+            check!(helper, matches!(set.join_next().await, Some(Ok(7))));
+
+            let (d_tx, mut d_rx) = oneshot::channel::<i32>();
+            let mut set = TaskSet::new(&exec);
+            let exec2 = exec.clone();
+            set.spawn(async move {
+                exec2.sleep_millis(50).await;
+                d_tx.send(1).unwrap();
+            });
+            drop(set);
+
+            exec.sleep_millis(100).await;
+            check!(helper, c_rx.try_recv().is_err()); // The sender was dropped with the cancelled task
+            check!(helper, d_rx.try_recv().is_err());
+        });
+    }
+
+    #[cfg(feature = "scoped")]
+    pub fn common_test_scope(manager: AgnosticExecutorManager, mut helper: TestHelper) {
+        struct DropFlag(std::sync::Arc<std::sync::atomic::AtomicBool>);
+
+        impl Drop for DropFlag {
+            fn drop(&mut self) {
+                self.0.store(true, std::sync::atomic::Ordering::SeqCst);
+            }
+        }
+
+        let exec = manager.get_executor();
+        manager.start(async move{
+            let values = vec![1i32, 2, 3];
+            let (c_tx, mut c_rx) = oneshot::channel::<i32>();
+
+            // Safety: every scope future is awaited or dropped
+            let res = unsafe { exec.scope(|s| {
+                for value in &values {
+                    let exec = exec.clone();
+                    s.spawn(async move {
+                        exec.sleep_millis(10 * (4 - *value as u64)).await; // Finish in reverse order
+                        (*value * 10, agnostic_async_executor::current_task_id().is_some())
+                    });
+                }
+            }) }.await;
+            check!(helper, res == vec![(10, true), (20, true), (30, true)]); // Spawned as tasks of the executor
+            check!(helper, values.len() == 3); // Still borrowed by the scope until here
+
+            let dropped = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+            let guard = DropFlag(dropped.clone());
+            let scope = unsafe { exec.scope(|s| {
+                s.spawn(async move {
+                    let _guard = guard;
+                    futures::future::pending::<()>().await;
+                    c_tx.send(1).unwrap();
+                });
+            }) };
+            let res = exec.timeout_millis(10, scope).await;
+            check!(helper, res.is_err());
+            check!(helper, dropped.load(std::sync::atomic::Ordering::SeqCst)); // Dropping the scope dropped the child
+            check!(helper, c_rx.try_recv().is_err());
+        });
+    }
+
     pub fn common_test_global(manager: AgnosticExecutorManager, mut helper: TestHelper) {
-        // We test global on a single test, because otherwise we would get mismatching global executors, as they can only be set once
+        // The executor is set as global by the test helper, overriding it on native and setting it once on wasm
+        let exec = manager.get_executor();
+        check!(helper, agnostic_async_executor::check_global_executor());
 
         // Block On
         #[cfg(feature = "block_on")]
@@ -151,10 +909,29 @@ pub(crate) mod common_tests {
             check!(helper, res == 1);
 
             // Spawn Blocking
-            let res = agnostic_async_executor::spawn_blocking(|| {
-                1i32
+            if supports_threads(&helper) {
+                let res = agnostic_async_executor::spawn_blocking(|| {
+                    1i32
+                }).await;
+                check!(helper, res == 1);
+            }
+
+            // Overridden on a task that might run on another thread
+            let res = exec.spawn(agnostic_async_executor::with_global_executor_async(&exec, async {
+                agnostic_async_executor::spawn(async { 2i32 }).await
+            })).await;
+            check!(helper, res == 2);
+
+            // Inherited by the tasks spawned from the test thread, wherever they run
+            let res = exec.spawn(async {
+                agnostic_async_executor::spawn(async { 3i32 }).await
             }).await;
-            check!(helper, res == 1);
+            check!(helper, res == 3);
+
+            if supports_threads(&helper) {
+                let res = exec.spawn_blocking(agnostic_async_executor::check_global_executor).await;
+                check!(helper, res);
+            }
         });
     }
 }
\ No newline at end of file
@@ -0,0 +1,26 @@
+#![ cfg(not(feature = "wasm_bindgen_executor")) ]
+
+// Async Std and async_global_executor share a pool that is configured once per process, so these tests need their own test binary
+
+use agnostic_async_executor::{new_agnostic_executor, RuntimeConfig, RuntimeOption};
+
+#[test]
+fn test_async_global_config_only_applies_once() {
+    let config = RuntimeConfig::new().worker_threads(2).thread_name("agnostic-global");
+    let manager = new_agnostic_executor().with_config(config.clone()).use_async_global_executor();
+    assert!(manager.ignored_options().is_empty());
+
+    let exec = manager.get_executor();
+    let name = manager.run(async move {
+        exec.spawn(async { std::thread::current().name().unwrap_or_default().to_owned() }).await
+    });
+    assert!(name.starts_with("agnostic-global-"));
+
+    // The pool is already configured, so the next managers can't apply any option
+    let manager = new_agnostic_executor().with_config(config.clone()).use_async_global_executor();
+    assert_eq!(manager.ignored_options(), &[RuntimeOption::WorkerThreads, RuntimeOption::ThreadName]);
+
+    let config = config.max_blocking_threads(4);
+    let manager = new_agnostic_executor().with_config(config).use_async_std_executor();
+    assert_eq!(manager.ignored_options(), &[RuntimeOption::WorkerThreads, RuntimeOption::ThreadName, RuntimeOption::MaxBlockingThreads]);
+}
@@ -17,6 +17,76 @@ pub(crate) mod common_tests {
         test_in_native(false, common_tests::common_test_spawn_blocking);
     }
 
+    #[test]
+    pub fn test_spawn_compute() {
+        test_in_native(false, common_tests::common_test_spawn_compute);
+    }
+
+    #[test]
+    pub fn test_spawn_rayon() {
+        test_in_native(false, common_tests::common_test_spawn_rayon);
+    }
+
+    #[test]
+    pub fn test_run() {
+        test_in_native(false, common_tests::common_test_run);
+    }
+
+    #[test]
+    pub fn test_task_builder() {
+        test_in_native(false, common_tests::common_test_task_builder);
+    }
+
+    #[test]
+    pub fn test_current_executor() {
+        test_in_native(false, common_tests::common_test_current_executor);
+    }
+
+    #[test]
+    pub fn test_from_ambient() {
+        test_in_native(false, common_tests::common_test_from_ambient);
+    }
+
+    #[test]
+    pub fn test_kind() {
+        test_in_native(false, common_tests::common_test_kind);
+    }
+
+    #[test]
+    pub fn test_task_local() {
+        test_in_native(false, common_tests::common_test_task_local);
+    }
+
+    #[test]
+    pub fn test_try_take() {
+        test_in_native(false, common_tests::common_test_try_take);
+    }
+
+    #[test]
+    pub fn test_abort_handle() {
+        test_in_native(false, common_tests::common_test_abort_handle);
+    }
+
+    #[test]
+    pub fn test_abort_on_drop() {
+        test_in_native(false, common_tests::common_test_abort_on_drop);
+    }
+
+    #[test]
+    pub fn test_shared_join_handle() {
+        test_in_native(false, common_tests::common_test_shared_join_handle);
+    }
+
+    #[test]
+    pub fn test_task_set() {
+        test_in_native(false, common_tests::common_test_task_set);
+    }
+
+    #[test]
+    pub fn test_scope() {
+        test_in_native(false, common_tests::common_test_scope);
+    }
+
     #[test]
     pub fn test_block_on() {
         test_in_native(false, common_tests::common_test_block_on);
@@ -37,11 +107,36 @@ pub(crate) mod common_tests {
         test_in_native(false, common_tests::common_test_interval);
     }
 
+    #[test]
+    pub fn test_virtual_time() {
+        test_in_native(false, common_tests::common_test_virtual_time);
+    }
+
+    #[test]
+    pub fn test_virtual_time_busy() {
+        test_in_native(false, common_tests::common_test_virtual_time_busy);
+    }
+
+    #[test]
+    pub fn test_virtual_time_blocking() {
+        test_in_native(false, common_tests::common_test_virtual_time_blocking);
+    }
+
+    #[test]
+    pub fn test_virtual_advance() {
+        test_in_native(false, common_tests::common_test_virtual_advance);
+    }
+
     #[test]
     pub fn test_local() {
         test_in_native(false, common_tests::common_test_local);
     }
 
+    #[test]
+    pub fn test_local_dropped() {
+        test_in_native(false, common_tests::common_test_local_dropped);
+    }
+
     #[test]
     pub fn test_cancel_handle() {
         test_in_native(false, common_tests::common_test_cancel_handle);
@@ -52,9 +147,101 @@ pub(crate) mod common_tests {
         test_in_native(false, common_tests::common_test_drop_handle);
     }
 
+    #[test]
+    pub fn test_try_join() {
+        test_in_native(false, common_tests::common_test_try_join);
+    }
+
+    #[test]
+    pub fn test_interleaving() {
+        test_in_native_with_seeds(false, 0..32, common_tests::common_test_interleaving);
+    }
+
+    #[test]
+    pub fn test_shutdown() {
+        test_in_native(false, common_tests::common_test_shutdown);
+    }
+
+    #[test]
+    pub fn test_shutdown_grace_period() {
+        test_in_native(false, common_tests::common_test_shutdown_grace_period);
+    }
+
+    #[test]
+    pub fn test_shutdown_root_cancelled() {
+        test_in_native(false, common_tests::common_test_shutdown_root_cancelled);
+    }
+
+    #[test]
+    pub fn test_shutdown_stuck_blocking() {
+        test_in_native(false, common_tests::common_test_shutdown_stuck_blocking);
+    }
+
     #[test]
     pub fn test_global() {
         test_in_native(true, common_tests::common_test_global);
     }
     
-}
\ No newline at end of file
+}
+
+#[cfg(all(test, feature = "simulation_executor"))]
+mod simulation_tests {
+    use std::sync::{Arc, Mutex};
+    use agnostic_async_executor::new_agnostic_executor;
+
+    fn run_order(seed: u64) -> Vec<usize> {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let manager = new_agnostic_executor().use_simulation_executor(seed);
+        let exec = manager.get_executor();
+        let res = order.clone();
+        manager.start(async move {
+            let handles: Vec<_> = (0..8).map(|i| {
+                let order = order.clone();
+                exec.spawn(async move { order.lock().unwrap().push(i) })
+            }).collect();
+            for handle in handles {
+                handle.await;
+            }
+        });
+        let res = res.lock().unwrap().clone();
+        res
+    }
+
+    #[test]
+    pub fn test_same_seed_same_order() {
+        for seed in 0..16 {
+            assert_eq!(run_order(seed), run_order(seed));
+        }
+    }
+
+    #[test]
+    pub fn test_seeds_change_order() {
+        let first = run_order(0);
+        assert!((1..16).any(|seed| run_order(seed) != first));
+    }
+}
+
+#[cfg(all(test, feature = "tokio_executor", feature = "block_on"))]
+mod scope_tests {
+    use std::sync::Barrier;
+    use agnostic_async_executor::{new_agnostic_executor, RuntimeConfig};
+
+    #[test]
+    pub fn test_scope_and_block_runs_in_parallel() {
+        let manager = new_agnostic_executor().with_config(RuntimeConfig::new().worker_threads(2)).use_tokio_executor();
+        let exec = manager.get_executor();
+
+        // Each child blocks its thread until the other one arrives, so they only finish if they run in parallel
+        let barrier = Barrier::new(2);
+        let res = exec.scope_and_block(|s| {
+            for i in 0..2 {
+                let barrier = &barrier;
+                s.spawn(async move {
+                    barrier.wait();
+                    i
+                });
+            }
+        });
+        assert_eq!(res, vec![0, 1]);
+    }
+}
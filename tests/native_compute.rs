@@ -0,0 +1,60 @@
+#![ cfg(not(feature = "wasm_bindgen_executor")) ]
+
+// The compute pool is shared by the whole process, so the tests that fill it up need their own test binary
+
+use std::{
+    sync::{Arc, atomic::{AtomicBool, Ordering}},
+    time::Duration
+};
+
+use agnostic_async_executor::new_agnostic_executor;
+
+#[test]
+fn test_compute_cancel_before_start() {
+    let manager = new_agnostic_executor().use_tokio_executor();
+    let exec = manager.get_executor();
+
+    let (ran, names) = manager.run(async move {
+        let release = Arc::new(AtomicBool::new(false));
+        let wait = move |release: Arc<AtomicBool>| {
+            while !release.load(Ordering::SeqCst) {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            std::thread::current().name().unwrap_or_default().to_owned()
+        };
+
+        // Occupy every thread of the pool, until a task doesn't start because the pool doesn't grow
+        let mut blockers = Vec::new();
+        loop {
+            let (started_tx, started_rx) = std::sync::mpsc::channel::<()>();
+            let release = release.clone();
+            blockers.push(exec.spawn_compute(move || {
+                let _ = started_tx.send(()); // The last one starts after its receiver is dropped
+                wait(release)
+            }));
+            exec.sleep_millis(200).await;
+            if started_rx.try_recv().is_err() {
+                break;
+            }
+        }
+
+        // Every thread is busy until the release, so this one is still queued when it's aborted
+        let ran = Arc::new(AtomicBool::new(false));
+        let ran_task = ran.clone();
+        let handle = exec.spawn_compute(move || ran_task.store(true, Ordering::SeqCst));
+        handle.abort_handle().abort();
+        release.store(true, Ordering::SeqCst);
+
+        let res = handle.try_join().await;
+        assert!(matches!(res, Err(e) if e.is_cancelled()));
+
+        let mut names = Vec::new();
+        for blocker in blockers {
+            names.push(blocker.await);
+        }
+        (ran.load(Ordering::SeqCst), names)
+    });
+
+    assert!(!ran);
+    assert!(names.iter().all(|name| name.starts_with("agnostic-compute")));
+}
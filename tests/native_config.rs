@@ -0,0 +1,176 @@
+#![ cfg(not(feature = "wasm_bindgen_executor")) ]
+
+// Runtime configuration of the executors that create their own thread pools
+
+use std::{
+    sync::{Arc, atomic::{AtomicUsize, Ordering}},
+    time::Duration
+};
+
+use agnostic_async_executor::{new_agnostic_executor, RuntimeConfig, RuntimeOption};
+
+fn thread_name() -> String {
+    std::thread::current().name().unwrap_or_default().to_owned()
+}
+
+#[test]
+fn test_tokio_config() {
+    let started = Arc::new(AtomicUsize::new(0));
+    let started_hook = started.clone();
+    let config = RuntimeConfig::new()
+        .worker_threads(2)
+        .thread_name("agnostic-tokio")
+        .max_blocking_threads(4)
+        .thread_keep_alive(Duration::from_secs(1))
+        .on_thread_start(move || { started_hook.fetch_add(1, Ordering::SeqCst); });
+    let manager = new_agnostic_executor().with_config(config).use_tokio_executor();
+    assert!(manager.ignored_options().is_empty());
+
+    let exec = manager.get_executor();
+    let name = manager.run(async move {
+        exec.spawn(async { thread_name() }).await
+    });
+    assert!(name.starts_with("agnostic-tokio-"));
+    assert!(started.load(Ordering::SeqCst) >= 2);
+}
+
+#[test]
+fn test_tokio_current_thread_config() {
+    let config = RuntimeConfig::new().worker_threads(2).thread_name("agnostic-blocking");
+    let manager = new_agnostic_executor().with_config(config).use_tokio_current_thread_executor();
+    assert_eq!(manager.ignored_options(), &[RuntimeOption::WorkerThreads]);
+
+    let exec = manager.get_executor();
+    let name = manager.run(async move {
+        exec.spawn_blocking(thread_name).await
+    });
+    assert!(name.starts_with("agnostic-blocking-"));
+}
+
+#[test]
+fn test_tokio_with_runtime_config() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let config = RuntimeConfig::new().worker_threads(2).thread_name("ignored");
+    let manager = new_agnostic_executor().with_config(config).use_tokio_executor_with_runtime(rt);
+    assert_eq!(manager.ignored_options(), &[RuntimeOption::WorkerThreads, RuntimeOption::ThreadName]);
+}
+
+#[test]
+fn test_smol_config() {
+    let started = Arc::new(AtomicUsize::new(0));
+    let stopped = Arc::new(AtomicUsize::new(0));
+    let (started_hook, stopped_hook) = (started.clone(), stopped.clone());
+    let config = RuntimeConfig::new()
+        .worker_threads(3)
+        .thread_name("ignored")
+        .on_thread_start(move || { started_hook.fetch_add(1, Ordering::SeqCst); })
+        .on_thread_stop(move || { stopped_hook.fetch_add(1, Ordering::SeqCst); });
+    let manager = new_agnostic_executor().with_config(config).use_smol_executor(None);
+    assert_eq!(manager.ignored_options(), &[RuntimeOption::ThreadName]);
+
+    let exec = manager.get_executor();
+    let res = manager.run(async move {
+        exec.spawn(async { 1i32 }).await
+    });
+    assert_eq!(res, 1);
+    assert_eq!(started.load(Ordering::SeqCst), 3);
+    assert_eq!(stopped.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn test_smol_single_thread_config() {
+    // The only thread is the one calling start, so the hooks are not run
+    let config = RuntimeConfig::new().on_thread_start(|| {}).on_thread_stop(|| {});
+    let manager = new_agnostic_executor().with_config(config).use_smol_executor(Some(1));
+    assert_eq!(manager.ignored_options(), &[RuntimeOption::OnThreadStart, RuntimeOption::OnThreadStop]);
+}
+
+#[test]
+fn test_futures_config() {
+    let config = RuntimeConfig::new()
+        .worker_threads(2)
+        .thread_name("agnostic-futures")
+        .thread_keep_alive(Duration::from_secs(1));
+    let manager = new_agnostic_executor().with_config(config).use_futures_executor();
+    assert!(manager.ignored_options().is_empty());
+
+    let exec = manager.get_executor();
+    let (name, blocking_name) = manager.run(async move {
+        (exec.spawn(async { thread_name() }).await, exec.spawn_blocking(thread_name).await)
+    });
+    assert!(name.starts_with("agnostic-futures"));
+    assert_eq!(blocking_name, "agnostic-futures");
+}
+
+#[test]
+fn test_futures_blocking_queue_limit() {
+    let config = RuntimeConfig::new().max_blocking_threads(1).blocking_queue_limit(1);
+    let manager = new_agnostic_executor().with_config(config).use_futures_executor();
+
+    let exec = manager.get_executor();
+    let (first, second, third) = manager.run(async move {
+        let (started_tx, started_rx) = std::sync::mpsc::channel::<()>();
+        let (tx, rx) = std::sync::mpsc::channel::<()>();
+        let first = exec.spawn_blocking(move || {
+            started_tx.send(()).unwrap();
+            rx.recv().is_ok()
+        });
+        // Wait for the only thread to take the first task, so the second one is the only one queued
+        while started_rx.try_recv().is_err() {
+            exec.sleep_millis(10).await;
+        }
+        let second = exec.spawn_blocking(|| 2i32);
+        let third = exec.spawn_blocking(|| 3i32);
+        tx.send(()).unwrap();
+        (first.try_join().await, second.try_join().await, third.try_join().await)
+    });
+    assert!(matches!(first, Ok(true)));
+    assert!(matches!(second, Ok(2)));
+    assert!(matches!(third, Err(e) if e.is_rejected()));
+}
+
+#[test]
+fn test_futures_blocking_queue_limit_zero() {
+    // Without a queue the tasks still run as long as there is a free thread for them
+    let config = RuntimeConfig::new().max_blocking_threads(2).blocking_queue_limit(0);
+    let manager = new_agnostic_executor().with_config(config).use_futures_executor();
+
+    let exec = manager.get_executor();
+    let (first, second) = manager.run(async move {
+        let (tx, rx) = std::sync::mpsc::channel::<()>();
+        let first = exec.spawn_blocking(move || rx.recv().is_ok());
+        let second = exec.spawn_blocking(|| 2i32).try_join().await;
+        tx.send(()).unwrap();
+        (first.try_join().await, second)
+    });
+    assert!(matches!(first, Ok(true)));
+    assert!(matches!(second, Ok(2)));
+}
+
+#[test]
+fn test_use_executor() {
+    use agnostic_async_executor::{ExecutorKind, ExecutorKindError, EXECUTOR_ENV_VAR};
+
+    assert_eq!("Async-Std".parse(), Ok(ExecutorKind::AsyncStd));
+    assert_eq!(ExecutorKind::AsyncGlobal.to_string().parse(), Ok(ExecutorKind::AsyncGlobal));
+    assert_eq!("unknown".parse::<ExecutorKind>(), Err(ExecutorKindError::Unknown("unknown".to_owned())));
+    assert_eq!(new_agnostic_executor().use_executor(ExecutorKind::WasmBindgen).err(), Some(ExecutorKindError::NotEnabled(ExecutorKind::WasmBindgen)));
+
+    for name in ["tokio", "async_std", "smol", "async_global", "simulation", "futures"] {
+        let kind = name.parse().unwrap();
+        let manager = new_agnostic_executor().use_executor(kind).unwrap();
+        let exec = manager.get_executor();
+        assert_eq!(exec.kind(), kind);
+        let res = manager.run(async move {
+            exec.spawn(async { 1i32 }).await
+        });
+        assert_eq!(res, 1);
+    }
+
+    // This is the only test using the variable
+    std::env::set_var(EXECUTOR_ENV_VAR, "smol");
+    let manager = new_agnostic_executor().from_env().unwrap();
+    assert_eq!(manager.get_executor().kind(), ExecutorKind::Smol);
+    std::env::remove_var(EXECUTOR_ENV_VAR);
+    assert_eq!(new_agnostic_executor().from_env().err(), Some(ExecutorKindError::NotSet));
+}
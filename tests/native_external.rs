@@ -0,0 +1,37 @@
+#![ cfg(not(feature = "wasm_bindgen_executor")) ]
+
+// Executors wrapping runtimes that are owned and driven outside of this crate
+
+use std::sync::Arc;
+
+use agnostic_async_executor::AgnosticExecutor;
+
+#[test]
+fn test_from_tokio_handle() {
+    let rt = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+    let exec = AgnosticExecutor::from_tokio_handle(rt.handle().clone());
+    let res = rt.block_on(async move {
+        exec.spawn(async { 1i32 }).await + exec.spawn_blocking(|| 1i32).await
+    });
+    assert_eq!(res, 2);
+}
+
+#[test]
+fn test_from_smol_executor() {
+    let executor = Arc::new(async_executor::Executor::new());
+    let exec = AgnosticExecutor::from_smol_executor(executor.clone());
+    let handle = exec.spawn(async { 1i32 });
+    let blocking_handle = exec.spawn_blocking(|| 1i32);
+    let res = futures_lite::future::block_on(executor.run(async { handle.await + blocking_handle.await }));
+    assert_eq!(res, 2);
+}
+
+#[test]
+fn test_from_futures_thread_pool() {
+    let pool = futures::executor::ThreadPool::new().unwrap();
+    let exec = AgnosticExecutor::from_futures_thread_pool(pool);
+    let res = futures::executor::block_on(async move {
+        exec.spawn(async { 1i32 }).await + exec.spawn_blocking(|| 1i32).await
+    });
+    assert_eq!(res, 2);
+}
@@ -0,0 +1,48 @@
+#![ cfg(not(feature = "wasm_bindgen_executor")) ]
+
+// Rayon pools shared between the application and the executors
+
+use std::sync::Arc;
+
+use agnostic_async_executor::{new_agnostic_executor, AgnosticExecutor};
+
+fn rayon_pool(name: &'static str) -> Arc<rayon::ThreadPool> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(2)
+        .thread_name(move |index| format!("{}-{}", name, index))
+        .build()
+        .unwrap();
+    Arc::new(pool)
+}
+
+fn thread_name() -> String {
+    std::thread::current().name().unwrap_or_default().to_owned()
+}
+
+#[test]
+fn test_manager_rayon_pool() {
+    let mut manager = new_agnostic_executor().use_smol_executor(None);
+    manager.set_rayon_pool(rayon_pool("app-rayon"));
+
+    let exec = manager.get_executor();
+    let (name, names) = manager.run(async move {
+        let name = exec.spawn_rayon(thread_name).await;
+        // The executor is also available from the tasks, with the same pool
+        let names = exec.spawn(async {
+            AgnosticExecutor::current().unwrap().par_map_async(0..4, |_| thread_name()).await
+        }).await;
+        (name, names)
+    });
+    assert!(name.starts_with("app-rayon"));
+    assert!(names.iter().all(|name| name.starts_with("app-rayon")));
+}
+
+#[test]
+fn test_with_rayon_pool() {
+    let rt = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+    let exec = AgnosticExecutor::from_tokio_handle(rt.handle().clone()).with_rayon_pool(rayon_pool("external-rayon"));
+    let name = rt.block_on(async move {
+        exec.spawn_rayon(thread_name).await
+    });
+    assert!(name.starts_with("external-rayon"));
+}
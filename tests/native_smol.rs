@@ -0,0 +1,28 @@
+#![ cfg(not(feature = "wasm_bindgen_executor")) ]
+
+// Behaviour specific to the Smol executor
+
+use std::{
+    sync::{Arc, atomic::{AtomicBool, Ordering}},
+    time::Duration
+};
+
+use agnostic_async_executor::new_agnostic_executor;
+
+#[test]
+fn test_smol_single_thread_runs_spawned_tasks() {
+    let manager = new_agnostic_executor().use_smol_executor(Some(1));
+    let exec = manager.get_executor();
+    let done = Arc::new(AtomicBool::new(false));
+    let done_root = done.clone();
+    manager.start(async move {
+        // If nothing drives the shared executor the task never runs, the timer makes the test fail instead of hanging
+        let task = async { exec.spawn(async { 1i32 }).await == 1 };
+        let timer = async {
+            async_io::Timer::after(Duration::from_secs(2)).await;
+            false
+        };
+        done_root.store(futures_lite::future::or(task, timer).await, Ordering::SeqCst);
+    });
+    assert!(done.load(Ordering::SeqCst));
+}
@@ -20,6 +20,71 @@ mod wasm_common_tests {
         test_in_wasm(common_tests::common_test_spawn_blocking).await;
     }
 
+    #[wasm_bindgen_test]
+    async fn test_spawn_compute() {
+        test_in_wasm(common_tests::common_test_spawn_compute).await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_spawn_rayon() {
+        test_in_wasm(common_tests::common_test_spawn_rayon).await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_task_builder() {
+        test_in_wasm(common_tests::common_test_task_builder).await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_current_executor() {
+        test_in_wasm(common_tests::common_test_current_executor).await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_from_ambient() {
+        test_in_wasm(common_tests::common_test_from_ambient).await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_kind() {
+        test_in_wasm(common_tests::common_test_kind).await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_task_local() {
+        test_in_wasm(common_tests::common_test_task_local).await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_try_take() {
+        test_in_wasm(common_tests::common_test_try_take).await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_abort_handle() {
+        test_in_wasm(common_tests::common_test_abort_handle).await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_abort_on_drop() {
+        test_in_wasm(common_tests::common_test_abort_on_drop).await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_shared_join_handle() {
+        test_in_wasm(common_tests::common_test_shared_join_handle).await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_task_set() {
+        test_in_wasm(common_tests::common_test_task_set).await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_scope() {
+        test_in_wasm(common_tests::common_test_scope).await;
+    }
+
     #[wasm_bindgen_test]
     async fn test_sleep() {
         test_in_wasm(common_tests::common_test_sleep).await;
@@ -35,6 +100,26 @@ mod wasm_common_tests {
         test_in_wasm(common_tests::common_test_interval).await;
     }
 
+    #[wasm_bindgen_test]
+    async fn test_virtual_time() {
+        test_in_wasm(common_tests::common_test_virtual_time).await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_virtual_time_busy() {
+        test_in_wasm(common_tests::common_test_virtual_time_busy).await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_virtual_time_blocking() {
+        test_in_wasm(common_tests::common_test_virtual_time_blocking).await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_virtual_advance() {
+        test_in_wasm(common_tests::common_test_virtual_advance).await;
+    }
+
     #[wasm_bindgen_test]
     async fn test_local() {
         test_in_wasm(common_tests::common_test_local).await;
@@ -50,6 +135,11 @@ mod wasm_common_tests {
         test_in_wasm(common_tests::common_test_drop_handle).await;
     }
 
+    #[wasm_bindgen_test]
+    async fn test_try_join() {
+        test_in_wasm(common_tests::common_test_try_join).await;
+    }
+
     #[wasm_bindgen_test]
     async fn test_global() {
         test_in_wasm(common_tests::common_test_global).await;